@@ -40,6 +40,8 @@ def import_fuzzy_value(in_dir):
     fuzzy_value_dict = {}
     with open(in_dir, "r", encoding="utf-8") as f:
         for line in f:
+            if line.startswith('#'):
+                continue
             line = line.replace("\n", "").split(',')
             fuzzy_value_dict[line.pop(0)] = line.pop()
     return fuzzy_value_dict
@@ -48,6 +50,8 @@ def import_node_index(in_dir):
     node_index_dict = {}
     with open(in_dir, "r", encoding="utf-8") as f:
         for line in f:
+            if line.startswith('#'):
+                continue
             line = line.replace("\n", "").split(',')
             node_index_dict[line.pop(0)] = line.pop()
     return node_index_dict
@@ -6,6 +6,8 @@ mkdir output/fuz_fpr
 mkdir output/inf_detect
 mkdir output/inf_dist
 mkdir output/rwc
+mkdir output/seed
+mkdir output/seed_recall
 mkdir output/thd_fpr_fix_step
 
 mkdir python/graphs
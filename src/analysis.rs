@@ -3,8 +3,12 @@
 mod utils {
     use std::{io::Write, fs::File};
 
-    pub fn write_val_vec_to_file<T: std::fmt::Debug> (list: &Vec<T>, dir: &String) {
+    // `header` is the seed line of the input the values were computed from
+    pub fn write_val_vec_to_file<T: std::fmt::Debug> (list: &Vec<T>, dir: &String, header: &Option<String>) {
         let mut f = File::create(dir).unwrap();
+        if let Some(header) = header {
+            let _ = writeln!(f, "{}", header);
+        }
         for fpr in list {
             let output = format!("{:?}\n", fpr);
             let _ = f.write_all(&output.as_bytes());
@@ -12,9 +16,11 @@ mod utils {
     }
 }
 
-mod traceability {
+pub(crate) mod traceability {
     use std::{collections::HashMap, io::{BufReader, BufRead}, f64, fs};
 
+    use crate::simulation::influence::InfRecord;
+
     pub fn import_csv(file_dir: &String) -> HashMap<usize, (usize,f64)> {
         let file = fs::File::open(file_dir).unwrap();
         let reader = BufReader::new(file);
@@ -22,7 +28,7 @@ mod traceability {
         for line in reader.lines() {
             let str_line = line.unwrap();
             let items: Vec<&str> = str_line.split(",").collect();
-            (items[0] != "id").then(|| {
+            (items[0] != "id" && !str_line.starts_with('#')).then(|| {
                 let id = items[0].to_string().parse::<usize>().unwrap();
                 let fuz = items[2].to_string().parse::<f64>().unwrap();
                 let inf = items[3].to_string().parse::<usize>().unwrap();
@@ -32,6 +38,65 @@ mod traceability {
         id_val_map
     }
 
+    // the same map from records made in-process by `simulation::influence`
+    pub fn records_to_map(records: &[InfRecord]) -> HashMap<usize, (usize,f64)> {
+        records.iter().map(|(id, _, fuz, inf)| (*id, (*inf, *fuz))).collect()
+    }
+
+    // the "# seed: .." line the simulation wrote, if any
+    pub fn import_header(file_dir: &String) -> Option<String> {
+        let file = fs::File::open(file_dir).unwrap();
+        BufReader::new(file).lines().map_while(Result::ok).find(|line| line.starts_with('#'))
+    }
+
+    // lines of (node_id, seed, traced value) from `gen_seed_data_file`, as node_id -> (seed, traced value)
+    pub fn import_seed_csv(file_dir: &String) -> HashMap<usize, (usize,f64)> {
+        let file = fs::File::open(file_dir).unwrap();
+        let reader = BufReader::new(file);
+        let mut id_val_map = HashMap::<usize, (usize,f64)>::new();
+        for line in reader.lines() {
+            let str_line = line.unwrap();
+            let items: Vec<&str> = str_line.split(",").collect();
+            (!str_line.starts_with('#') && items.len() == 3).then(|| {
+                let id = items[0].to_string().parse::<usize>().unwrap();
+                let seed = items[1].to_string().parse::<usize>().unwrap();
+                let fuz = items[2].to_string().parse::<f64>().unwrap();
+                id_val_map.insert(id, (seed,fuz));
+            });
+        }
+        id_val_map
+    }
+
+    // (seed, is the seed itself traced, traced users it reached, users it reached, recall)
+    pub type SeedRecall = (usize, bool, usize, usize, f64);
+
+    // per threshold and per seed
+    pub fn seed_recall(hmap: &HashMap<usize,(usize,f64)>, thd_list: &[f64]) -> Vec::<Vec<SeedRecall>> {
+        let mut seeds: Vec<usize> = hmap.values().map(|(seed,_)| *seed).collect();
+        seeds.sort();
+        seeds.dedup();
+        let mut thd_recall_list = Vec::<Vec<SeedRecall>>::new();
+
+        thd_list.iter().for_each(|thd| {
+            let recall_list = seeds.iter().map(|seed| {
+                let fuz_list: Vec<f64> = hmap.values()
+                    .filter(|(src,_)| src == seed)
+                    .map(|(_,fuz)| *fuz)
+                    .collect();
+                let traced = fuz_list.iter().filter(|fuz| **fuz >= *thd).count();
+                let is_found = hmap.get(seed).is_some_and(|(_,fuz)| *fuz >= *thd);
+                (*seed, is_found, traced, fuz_list.len(), (traced as f64) / (fuz_list.len() as f64))
+            }).collect();
+            thd_recall_list.push(recall_list);
+        });
+        thd_recall_list
+    }
+
+    // true for each threshold where every seed itself is traced
+    pub fn all_seeds_found(recall_list: &[Vec<SeedRecall>]) -> Vec<bool> {
+        recall_list.iter().map(|list| list.iter().all(|(_, is_found, _, _, _)| *is_found)).collect()
+    }
+
     pub fn find_thd_fpr(hmap: &HashMap<usize,(usize,f64)>) -> Vec::<(f64, f64)> {
         let max_inf = find_max_inf_level(&hmap);
         let mut thd_fpr_list = Vec::<(f64, f64)>::new();
@@ -159,19 +224,46 @@ mod traceability {
 }
 
 mod tests {
-    use super::{traceability::{correctness, import_csv, traceability, inf_dist, privacy}, utils::write_val_vec_to_file};
+    use std::collections::HashMap;
+    use super::{traceability::{correctness, import_csv, import_header, import_seed_csv, traceability, inf_dist, privacy, seed_recall, all_seeds_found}, utils::write_val_vec_to_file};
+    use crate::experiment::spec::Spec;
 
     extern crate test;
 
     #[test]
     fn gen_graph_csv() {
-        let thd_list = vec![99.99, 99.995, 99.9995, 99.99995, 99.999995, 99.9999995, 99.99999995, 99.999999995, 100.0];
-        let range_list = vec![0.0, 80.0, 90.0, 95.0, 99.0, 99.5, 99.9, 99.99, 100.0];
-        let val_list = import_csv(&"python/inputs/fuz_val_and_inf.csv".to_string());
+        let spec = Spec::load("experiments/utility.toml").unwrap();
+        let (thd_list, range_list) = (spec.thresholds, spec.ranges);
+        let csv_dir = "python/inputs/fuz_val_and_inf.csv".to_string();
+        let val_list = import_csv(&csv_dir);
+        let header = import_header(&csv_dir);
+
+        write_val_vec_to_file(&inf_dist(&val_list), &"output/inf_dist/k_shell.txt".to_string(), &header);
+        write_val_vec_to_file(&traceability(&val_list, &thd_list), &"output/thd_fpr_fix_step/thd_fpr.txt".to_string(), &header);
+        write_val_vec_to_file(&correctness(&val_list, &thd_list), &"output/inf_detect/inf_detect.txt".to_string(), &header);
+        write_val_vec_to_file(&privacy(&val_list, &range_list), &"output/fuz_fpr/fuz_fpr.txt".to_string(), &header);
+    }
 
-        write_val_vec_to_file(&inf_dist(&val_list), &"output/inf_dist/k_shell.txt".to_string());
-        write_val_vec_to_file(&traceability(&val_list, &thd_list), &"output/thd_fpr_fix_step/thd_fpr.txt".to_string());
-        write_val_vec_to_file(&correctness(&val_list, &thd_list), &"output/inf_detect/inf_detect.txt".to_string());
-        write_val_vec_to_file(&privacy(&val_list, &range_list), &"output/fuz_fpr/fuz_fpr.txt".to_string());
+    #[test]
+    fn gen_seed_recall() {
+        let thd_list = Spec::load("experiments/utility.toml").unwrap().thresholds;
+        for (name, thd_list) in [("fuzzy", thd_list), ("trace", vec![100.0])] {
+            let csv_dir = format!("output/seed/0-false-0-{}.txt", name);
+            let val_list = import_seed_csv(&csv_dir);
+            let header = import_header(&csv_dir);
+            let recall = seed_recall(&val_list, &thd_list);
+            write_val_vec_to_file(&recall, &format!("output/seed_recall/{}.txt", name), &header);
+            write_val_vec_to_file(&all_seeds_found(&recall), &format!("output/seed_recall/{}_all_found.txt", name), &header);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_seed_recall() {
+        // seeds 1 and 2, 2 and one of the users it reached were not traced
+        let val_list = HashMap::from([(1, (1, 100.0)), (3, (1, 100.0)), (2, (2, 0.0)), (4, (2, 100.0)), (5, (2, 0.0))]);
+        let recall = seed_recall(&val_list, &[99.0]);
+        assert_eq!(recall[0][0], (1, true, 2, 2, 1.0));
+        assert_eq!((recall[0][1].0, recall[0][1].1, recall[0][1].2, recall[0][1].3), (2, false, 1, 3));
+        assert_eq!(all_seeds_found(&recall), vec![false]);
+    }
+}
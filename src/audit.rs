@@ -0,0 +1,157 @@
+#![allow(dead_code)]
+
+// Every traced report and every tag query made for it goes to a hash-chained log,
+// so removing or editing an entry breaks the chain from that point on.
+pub mod audit_log {
+    use serde::{Serialize, Deserialize};
+
+    use crate::db::db_audit;
+    use crate::message::messaging::MsgReport;
+    use crate::trace::traceback::TagQuery;
+    use crate::trace::trace_scope::TraceScope;
+    use crate::tool::utils::{hash_array_32, now};
+    use crate::warrant::trace_warrant::report_hash;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum AuditEvent {
+        TraceStarted { operator: String, report_hash: [u8; 16], scope: TraceScope },
+        TagQuery { operator: String, report_hash: [u8; 16], tags: usize, digest: [u8; 16] },
+        TraceFinished { operator: String, report_hash: [u8; 16], complete: bool, result_digest: [u8; 16] },
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct AuditEntry {
+        pub seq: u64,
+        pub ts: u64,
+        pub event: AuditEvent,
+        pub prev: [u8; 32],
+        pub hash: [u8; 32],
+    }
+
+    impl AuditEntry {
+        fn digest(seq: &u64, ts: &u64, event: &AuditEvent, prev: &[u8; 32]) -> [u8; 32] {
+            hash_array_32(serde_json::to_string(&(seq, ts, event, prev)).unwrap().as_bytes())
+        }
+
+        pub fn next(last: Option<&AuditEntry>, event: AuditEvent) -> Self {
+            let (seq, prev) = match last {
+                Some(last) => (last.seq + 1, last.hash),
+                None => (0, [0; 32]),
+            };
+            let ts = now();
+            let hash = AuditEntry::digest(&seq, &ts, &event, &prev);
+            AuditEntry { seq, ts, event, prev, hash }
+        }
+
+        pub fn is_intact(&self) -> bool {
+            AuditEntry::digest(&self.seq, &self.ts, &self.event, &self.prev) == self.hash
+        }
+    }
+
+    // the caller decides what to do when the log cannot be written, nothing is traced unlogged
+    pub fn append(event: AuditEvent) -> redis::RedisResult<AuditEntry> {
+        let mut appended: Option<AuditEntry> = None;
+        db_audit::append(|last| {
+            let last: Option<AuditEntry> = last.map(|entry| serde_json::from_str(&entry).unwrap());
+            let entry = AuditEntry::next(last.as_ref(), event.clone());
+            let json = serde_json::to_string(&entry).unwrap();
+            appended = Some(entry);
+            json
+        })?;
+        Ok(appended.unwrap())
+    }
+
+    pub fn load() -> Vec<AuditEntry> {
+        db_audit::entries().iter().map(|entry| serde_json::from_str(entry).unwrap()).collect()
+    }
+
+    // who runs a trace on which report, carried along by the trace state
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct AuditContext {
+        operator: String,
+        report_hash: [u8; 16],
+    }
+
+    impl AuditContext {
+        pub fn start(operator: &str, report: &MsgReport, scope: &TraceScope) -> redis::RedisResult<Self> {
+            let ctx = AuditContext { operator: operator.to_string(), report_hash: report_hash(report) };
+            append(AuditEvent::TraceStarted { operator: ctx.operator.clone(), report_hash: ctx.report_hash, scope: *scope })?;
+            Ok(ctx)
+        }
+
+        // a resumed trace keeps logging for the operator and report it was started with
+        pub fn is_for(&self, operator: &str, report: &MsgReport) -> bool {
+            (self.operator == operator) & (self.report_hash == report_hash(report))
+        }
+
+        pub fn tag_query(&self, query: &TagQuery) -> redis::RedisResult<()> {
+            append(AuditEvent::TagQuery { operator: self.operator.clone(), report_hash: self.report_hash, tags: query.lookups, digest: query.digest })?;
+            Ok(())
+        }
+
+        pub fn finished(&self, complete: bool, result_digest: &[u8; 16]) -> redis::RedisResult<()> {
+            append(AuditEvent::TraceFinished { operator: self.operator.clone(), report_hash: self.report_hash, complete, result_digest: *result_digest })?;
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum AuditError {
+        // the entry at this position no longer matches its hash
+        Edited(u64),
+        // an entry is missing right before this position
+        Removed(u64),
+        // the log ends before the published head
+        Truncated(u64),
+    }
+
+    // `head` is the (seq, hash) of an entry published to the auditors earlier, which also catches a cut-off tail
+    pub fn verify(entries: &[AuditEntry], head: Option<(u64, [u8; 32])>) -> Result<(), AuditError> {
+        let mut prev: [u8; 32] = [0; 32];
+        for (i, entry) in entries.iter().enumerate() {
+            let i = i as u64;
+            if !entry.is_intact() {
+                return Err(AuditError::Edited(i));
+            }
+            if (entry.seq != i) | (entry.prev != prev) {
+                return Err(AuditError::Removed(i));
+            }
+            prev = entry.hash;
+        }
+        match head {
+            Some((seq, hash)) => match entries.get(seq as usize) {
+                None => Err(AuditError::Truncated(seq)),
+                Some(entry) if entry.hash != hash => Err(AuditError::Edited(seq)),
+                Some(_) => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::audit_log::{self, AuditEntry, AuditEvent, AuditError};
+    use crate::trace::trace_scope::TraceScope;
+
+    #[test]
+    fn audit_chain_verify() {
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        for i in 0..4 {
+            let event = AuditEvent::TraceStarted { operator: format!("mod-{}", i), report_hash: [i; 16], scope: TraceScope::OriginOnly };
+            entries.push(AuditEntry::next(entries.last(), event));
+        }
+        let head = Some((3, entries[3].hash));
+        assert_eq!(audit_log::verify(&entries, head), Ok(()));
+
+        let mut edited = entries.clone();
+        edited[1].event = AuditEvent::TraceStarted { operator: "mod-x".to_string(), report_hash: [1; 16], scope: TraceScope::FullSpread };
+        assert_eq!(audit_log::verify(&edited, head), Err(AuditError::Edited(1)));
+
+        let mut removed = entries.clone();
+        removed.remove(2);
+        assert_eq!(audit_log::verify(&removed, head), Err(AuditError::Removed(2)));
+        assert_eq!(audit_log::verify(&entries[..3], head), Err(AuditError::Truncated(3)));
+    }
+}
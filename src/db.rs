@@ -45,21 +45,73 @@ pub mod db_tag {
         result
     }
 
+    // an empty pack, e.g. a user whose identity key the warrant does not open, is answered without a query
     pub fn mexists_pack(pack_keys: &Vec<Vec<String>>) -> Vec<Vec<bool>> {
         let mut conn = get_set_conn().unwrap();
         let set_name: String = env::var("DB_TAG_SET_NAME").expect("DB_TAG_SET_NAME is undefined.");
         let mut pipe = redis::pipe();
 
-        for keys in pack_keys {
-            if keys.len() == 0 {
-                panic!("keys.len() == 0");
-            }
+        for keys in pack_keys.iter().filter(|keys| !keys.is_empty()) {
             let command = redis::cmd("SMISMEMBER").arg(set_name.clone()).arg(keys.to_owned()).to_owned();
             pipe.add_command(command);
         }
-        let result: Vec<Vec<bool>> = pipe.query(&mut conn).unwrap();
-        result
+        let mut result = match pack_keys.iter().any(|keys| !keys.is_empty()) {
+            true => pipe.query::<Vec<Vec<bool>>>(&mut conn).unwrap().into_iter(),
+            false => Vec::new().into_iter(),
+        };
+        pack_keys.iter().map(|keys| match keys.is_empty() {
+            true => Vec::new(),
+            false => result.next().unwrap(),
+        }).collect()
+    }
+
+    fn anchor_key(anchor: &String) -> String {
+        format!("anchor:{}", anchor)
+    }
+
+    // bind a hop counter anchor to the tag of the hop that started it, true if it is bound to that tag
+    pub fn bind_anchor(anchor: &String, tag: &String) -> bool {
+        let mut conn = get_set_conn().unwrap();
+        let (_, bound): (bool, String) = redis::pipe()
+            .cmd("SETNX").arg(anchor_key(anchor)).arg(tag)
+            .cmd("GET").arg(anchor_key(anchor))
+            .query(&mut conn).unwrap();
+        bound == *tag
+    }
+
+    pub fn anchor_bound(anchor: &String) -> bool {
+        let mut conn = get_set_conn().unwrap();
+        redis::cmd("EXISTS").arg(anchor_key(anchor)).query(&mut conn).unwrap()
+    }
+
+    fn ts_name(set_name: &str) -> String {
+        format!("{}:ts", set_name)
+    }
+
+    // group tags also keep the time of the send, which fixes the members the send reached
+    pub fn add_timed(tags: &[(String, u64)]) -> redis::RedisResult<()> {
+        let mut conn = get_set_conn().unwrap();
+        let set_name: String = env::var("DB_TAG_SET_NAME").expect("DB_TAG_SET_NAME is undefined.");
+        let mut pipe = redis::pipe();
+
+        for (tag, ts) in tags {
+            pipe.add_command(redis::cmd("SADD").arg(&set_name).arg(tag).to_owned());
+            pipe.add_command(redis::cmd("HSET").arg(ts_name(&set_name)).arg(tag).arg(ts).to_owned());
+        }
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    // None for a tag stored without a time
+    pub fn timestamps(tags: &[String]) -> Vec<Option<u64>> {
+        if tags.is_empty() {
+            return Vec::new();
+        }
+        let mut conn = get_set_conn().unwrap();
+        let set_name: String = env::var("DB_TAG_SET_NAME").expect("DB_TAG_SET_NAME is undefined.");
+        redis::cmd("HMGET").arg(ts_name(&set_name)).arg(tags).query(&mut conn).unwrap()
     }
+
     pub fn clear() {
         let mut db_conn = get_set_conn().unwrap();
         let _: () = redis::cmd("FLUSHDB").query(&mut db_conn).unwrap();
@@ -77,7 +129,10 @@ pub mod db_ik {
     use std::env;
     use redis::Connection;
     use lazy_static::lazy_static;
-    use crate::message::messaging::IdKey;
+    use x25519_dalek::PublicKey;
+    use crate::message::messaging::{Edge, IdKey, Uid};
+    use crate::message::sealed_sender::SealedBox;
+    use crate::db::db_nbr;
 
     lazy_static! {
         pub static ref DB_IK_CONN: redis::Client = create_redis_client();
@@ -94,41 +149,147 @@ pub mod db_ik {
         Ok(redis_client?)
     }
 
+    fn sealed_key(uid: &Uid) -> String {
+        format!("sik:{}", uid)
+    }
+
+    // Identity keys are only kept sealed to the moderators' key, so only a trace warrant opens them.
     // write BRANCH users at one time
-    pub fn add(vec_id_key: &Vec<IdKey>) -> redis::RedisResult<()> {
-        let mut conn = get_redis_conn().unwrap();
+    pub fn add(vec_id_key: &[IdKey], pk: &PublicKey) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn()?;
         let mut pipe = redis::Pipeline::new();
 
-        for user in vec_id_key {
-            let ik = base64::encode(user.key);
-            let command = redis::cmd("SET").arg(user.id).arg(ik).to_owned();
-            pipe.add_command(command);
+        let sealed: Vec<(Uid, String)> = vec_id_key.iter()
+            .map(|user| (user.id, serde_json::to_string(&SealedBox::seal_key(&user.key, pk)).unwrap()))
+            .collect();
+        for (id, sealed) in &sealed {
+            pipe.add_command(redis::cmd("SET").arg(sealed_key(id)).arg(sealed).to_owned());
         }
         let _ : () = pipe.query(&mut conn)?;
-        Ok(())
+        db_nbr::add_ik(&sealed)
     }
 
-    pub fn query(vec_id: &Vec<u32>) -> HashMap<u32,[u8;16]> {
+    // ids without a registered key are left out
+    pub fn query_sealed(vec_id: &[Uid]) -> HashMap<Uid,SealedBox> {
         let mut conn = get_redis_conn().unwrap();
         let mut pipe = redis::Pipeline::new();
 
         for id in vec_id {
-            let command = redis::cmd("GET").arg(id).to_owned();
-            pipe.add_command(command);
+            pipe.add_command(redis::cmd("GET").arg(sealed_key(id)).to_owned());
+        }
+        let sealed: Vec<Option<String>> = pipe.query(&mut conn).unwrap();
+        vec_id.iter().copied().zip(sealed)
+            .filter_map(|(id, sealed)| Some((id, serde_json::from_str(&sealed?).ok()?)))
+            .collect()
+    }
+
+    // whether each id has a registered identity key
+    pub fn exists(vec_id: &[Uid]) -> Vec<bool> {
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        for id in vec_id {
+            pipe.add_command(redis::cmd("EXISTS").arg(sealed_key(id)).to_owned());
         }
-        let id_keys: Vec<String> = pipe.query(&mut conn).unwrap();
-        let mut map_id_key = HashMap::<u32,[u8;16]>::new();
-        for i in 0..vec_id.len() {
-            let q_ik = id_keys.get(i).unwrap();
-            let ik: [u8;16] = base64::decode(q_ik).unwrap().try_into().unwrap();
-            map_id_key.insert(*vec_id.get(i).unwrap(), ik);
+        pipe.query(&mut conn).unwrap()
+    }
+
+    // A session's hk = H(tk) is enough to process and check its tags, but not to step along a chain,
+    // which needs tk itself. A group session is kept under (sender, group).
+    fn hk_key(sess: &Edge) -> String {
+        format!("hk:{}:{}", sess.sid, sess.rid)
+    }
+
+    pub fn add_hk(sessions: &[(Edge, [u8; 16])]) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn()?;
+        let mut pipe = redis::Pipeline::new();
+
+        for (sess, hk) in sessions {
+            pipe.add_command(redis::cmd("SET").arg(hk_key(sess)).arg(base64::encode(hk)).to_owned());
         }
-        map_id_key
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    // None for a session that was never opened
+    pub fn query_hk(sess: &Edge) -> Option<[u8; 16]> {
+        let mut conn = get_redis_conn().unwrap();
+        let hk: Option<String> = redis::cmd("GET").arg(hk_key(sess)).query(&mut conn).unwrap();
+        base64::decode(hk?).ok()?.try_into().ok()
     }
 
+    // together with the copies mirrored in db_nbr
     pub fn clear() {
         let mut db_conn = get_redis_conn().unwrap();
         let _: () = redis::cmd("FLUSHDB").query(&mut db_conn).unwrap();
+        let _ = db_nbr::clear_ik();
+    }
+}
+
+// Approvals of trace warrants, one list per report, kept in the audit database.
+pub mod db_warrant {
+    extern crate redis;
+
+    use crate::db::db_audit::get_redis_conn;
+    use crate::warrant::trace_warrant::Approval;
+
+    fn approvals_key(report_hash: &[u8; 16]) -> String {
+        format!("warrant:{}", hex::encode(report_hash))
+    }
+
+    pub fn record(approval: &Approval) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn()?;
+        let entry = serde_json::to_string(approval).unwrap();
+        redis::cmd("RPUSH").arg(approvals_key(&approval.report_hash)).arg(entry).query(&mut conn)
+    }
+
+    pub fn approvals(report_hash: &[u8; 16]) -> Vec<Approval> {
+        let mut conn = get_redis_conn().unwrap();
+        let entries: Vec<String> = redis::cmd("LRANGE").arg(approvals_key(report_hash)).arg(0).arg(-1).query(&mut conn).unwrap();
+        entries.iter().map(|entry| serde_json::from_str(entry).unwrap()).collect()
+    }
+}
+
+// The audit log of tracing, an append-only list. It has a database of its own, shared with the
+// warrant approvals, so clearing the identity keys never touches it.
+pub mod db_audit {
+    extern crate redis;
+    extern crate lazy_static;
+
+    use std::env;
+    use dotenv::dotenv;
+    use redis::Connection;
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        pub static ref DB_AUDIT_CONN: redis::Client = create_redis_client();
+    }
+
+    fn create_redis_client() -> redis::Client {
+        dotenv().ok();
+        let db_audit_ip: String = env::var("DB_AUDIT_IP").expect("DB_AUDIT_IP is undefined.");
+        redis::Client::open(db_audit_ip).unwrap()
+    }
+
+    pub fn get_redis_conn() -> redis::RedisResult<Connection> {
+        DB_AUDIT_CONN.get_connection()
+    }
+
+    const AUDIT_LOG: &str = "audit";
+
+    // `entry` builds the new entry from the last one and is called again if another writer got in between
+    pub fn append<F: FnMut(Option<String>) -> String>(mut entry: F) -> redis::RedisResult<u64> {
+        let mut conn = get_redis_conn()?;
+        redis::transaction(&mut conn, &[AUDIT_LOG], |conn, pipe| {
+            let last: Option<String> = redis::cmd("LINDEX").arg(AUDIT_LOG).arg(-1).query(conn)?;
+            let len: Option<(u64,)> = pipe.cmd("RPUSH").arg(AUDIT_LOG).arg(entry(last)).query(conn)?;
+            Ok(len.map(|(len,)| len))
+        })
+    }
+
+    pub fn entries() -> Vec<String> {
+        let mut conn = get_redis_conn().unwrap();
+        redis::cmd("LRANGE").arg(AUDIT_LOG).arg(0).arg(-1).query(&mut conn).unwrap()
     }
 }
 
@@ -140,13 +301,37 @@ pub mod db_nbr {
     use std::collections::HashMap;
     use std::env;
 
-    use redis::Connection;
+    use redis::{Commands, Connection};
     use dotenv::dotenv;
     use lazy_static::lazy_static;
-    use crate::message::messaging::Edge;
+    use crate::message::messaging::{Edge, Uid};
+    use crate::message::sealed_sender::SealedBox;
+    use crate::db::{db_dev, db_grp};
+    use crate::db::db_grp::MemberEvent;
 
     lazy_static! {
         pub static ref DB_NBR_CONN: redis::Client = create_redis_client();
+        // KEYS = [group set, ik:<nbr>..., history:<nbr>...], ARGV = nbrs
+        static ref NBR_SCRIPT: redis::Script = redis::Script::new(r#"
+            local n = #ARGV
+            local rows = {}
+            for i, nbr in ipairs(ARGV) do
+                local history = false
+                if redis.call('SISMEMBER', KEYS[1], nbr) == 1 then
+                    history = redis.call('LRANGE', KEYS[1 + n + i], 0, -1)
+                end
+                rows[i] = {redis.call('GET', KEYS[1 + i]) or '', history}
+            end
+            return rows
+        "#);
+    }
+
+    // the sealed identity keys are mirrored next to the neighbor sets, so a search step needs
+    // one pipeline for the users and one script call for their nbrs
+    const IK_PREFIX: &str = "ik:";
+
+    fn ik_key(uid: &Uid) -> String {
+        format!("{}{}", IK_PREFIX, uid)
     }
 
     fn create_redis_client() -> redis::Client {
@@ -176,7 +361,97 @@ pub mod db_nbr {
         Ok(())
     }
 
-    pub fn query(vec_uid: &Vec<u32>) -> HashMap<u32,Vec<u32>> {
+    // expand user-level sessions to every pair of their devices, and link each user's own devices
+    pub fn add_device_sessions(edges: &[Edge]) -> redis::RedisResult<()> {
+        let mut users: Vec<Uid> = edges.iter().flat_map(|e| [e.sid, e.rid]).collect();
+        users.sort();
+        users.dedup();
+        let map_uid_devs = db_dev::devices(&users);
+
+        let mut dev_edges: Vec<Edge> = Vec::new();
+        for e in edges {
+            for snd in map_uid_devs.get(&e.sid).unwrap() {
+                for rcv in map_uid_devs.get(&e.rid).unwrap() {
+                    dev_edges.push(Edge::new(snd, rcv));
+                }
+            }
+        }
+        for devs in map_uid_devs.values() {
+            for i in 0..devs.len() {
+                for j in (i + 1)..devs.len() {
+                    dev_edges.push(Edge::new(&devs[i], &devs[j]));
+                }
+            }
+        }
+        add(&dev_edges)
+    }
+
+    // written by db_ik::add with the sealed keys it stores
+    pub(crate) fn add_ik(sealed: &[(Uid, String)]) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn()?;
+        let mut pipe = redis::Pipeline::new();
+
+        for (id, sealed) in sealed {
+            pipe.add_command(redis::cmd("SET").arg(ik_key(id)).arg(sealed).to_owned());
+        }
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    pub(crate) fn clear_ik() -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn()?;
+        let keys: Vec<String> = conn.scan_match::<_, String>(format!("{}*", IK_PREFIX))?.collect::<redis::RedisResult<_>>()?;
+        match keys.is_empty() {
+            true => Ok(()),
+            false => redis::cmd("DEL").arg(keys).query(&mut conn),
+        }
+    }
+
+    // None for a missing or unreadable key
+    fn decode_ik(sealed: &str) -> Option<SealedBox> {
+        serde_json::from_str(sealed).ok()
+    }
+
+    // a user's sealed identity key and its nbrs as (nbr, sealed identity key, membership history if the nbr is a group)
+    pub type FrontierRow = (Option<SealedBox>, Vec<(Uid, Option<SealedBox>, Option<Vec<MemberEvent>>)>);
+    type NbrRow = (Option<SealedBox>, Option<Vec<MemberEvent>>);
+
+    // every key the script reads is passed in KEYS, so the nbrs are only known to it after the users are read
+    pub fn query_frontier(vec_uid: &[Uid]) -> redis::RedisResult<Vec<FrontierRow>> {
+        if vec_uid.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut conn = get_redis_conn()?;
+        let mut pipe = redis::pipe();
+        for uid in vec_uid {
+            pipe.cmd("SMEMBERS").arg(uid).cmd("GET").arg(ik_key(uid));
+        }
+        let users: Vec<(Vec<Uid>, Option<String>)> = pipe.query(&mut conn)?;
+
+        let mut nbrs: Vec<Uid> = users.iter().flat_map(|(nbrs, _)| nbrs.iter().copied()).collect();
+        nbrs.sort();
+        nbrs.dedup();
+        let mut map_nbr_row: HashMap<Uid, NbrRow> = HashMap::new();
+        if !nbrs.is_empty() {
+            let rows: Vec<(String, Option<Vec<String>>)> = NBR_SCRIPT.key(db_grp::GROUP_SET)
+                .key(nbrs.iter().map(ik_key).collect::<Vec<String>>())
+                .key(nbrs.iter().map(db_grp::history_key).collect::<Vec<String>>())
+                .arg(&nbrs)
+                .invoke(&mut conn)?;
+            map_nbr_row = nbrs.into_iter().zip(rows)
+                .map(|(nbr, (ik, history))| (nbr, (decode_ik(&ik), history.map(|h| db_grp::parse_history(&h)))))
+                .collect();
+        }
+        Ok(users.into_iter().map(|(nbrs, ik)| {
+            let nbrs = nbrs.into_iter().map(|nbr| {
+                let (nbr_ik, history) = map_nbr_row[&nbr].clone();
+                (nbr, nbr_ik, history)
+            }).collect();
+            (ik.as_deref().and_then(decode_ik), nbrs)
+        }).collect())
+    }
+
+    pub fn query(vec_uid: &Vec<Uid>) -> HashMap<Uid,Vec<Uid>> {
         let mut conn = get_redis_conn().unwrap();
         let mut pipe = redis::Pipeline::new();
 
@@ -184,9 +459,9 @@ pub mod db_nbr {
             let command = redis::cmd("SMEMBERS").arg(uid).to_owned();
             pipe.add_command(command);
         });
-        let result: Vec<Vec<u32>> = pipe.query(&mut conn).unwrap();
-        // combine vec_uid and result to HashMap HashMap<u32,Vec<u32>>
-        let mut map_uid_nbr: HashMap<u32,Vec<u32>> = HashMap::new();
+        let result: Vec<Vec<Uid>> = pipe.query(&mut conn).unwrap();
+        // combine vec_uid and result to HashMap HashMap<Uid,Vec<Uid>>
+        let mut map_uid_nbr: HashMap<Uid,Vec<Uid>> = HashMap::new();
         for i in 0..vec_uid.len() {
             map_uid_nbr.insert(*vec_uid.get(i).unwrap(), result.get(i).unwrap().to_vec());
         }
@@ -200,6 +475,182 @@ pub mod db_nbr {
 
 }
 
+// Devices are sub-identities with their own key in db_ik, registered under the user who owns them.
+pub mod db_dev {
+    extern crate redis;
+
+    use std::collections::HashMap;
+
+    use x25519_dalek::PublicKey;
+    use crate::db::db_ik::{self, get_redis_conn};
+    use crate::message::messaging::{IdKey, Uid};
+
+    fn owner_key(did: &Uid) -> String {
+        format!("dev:{}", did)
+    }
+
+    fn devices_key(uid: &Uid) -> String {
+        format!("devs:{}", uid)
+    }
+
+    pub fn register(uid: &Uid, devices: &Vec<IdKey>, pk: &PublicKey) -> redis::RedisResult<()> {
+        db_ik::add(devices, pk)?;
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        for dev in devices {
+            pipe.add_command(redis::cmd("SET").arg(owner_key(&dev.id)).arg(uid).to_owned());
+            pipe.add_command(redis::cmd("SADD").arg(devices_key(uid)).arg(dev.id).to_owned());
+        }
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    // a user without registered devices acts as its own single device
+    pub fn devices(vec_uid: &[Uid]) -> HashMap<Uid,Vec<Uid>> {
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        vec_uid.iter().for_each(|uid| {
+            pipe.add_command(redis::cmd("SMEMBERS").arg(devices_key(uid)).to_owned());
+        });
+        let result: Vec<Vec<Uid>> = pipe.query(&mut conn).unwrap();
+        vec_uid.iter().copied().zip(result)
+            .map(|(uid, devs)| match devs.is_empty() {
+                true => (uid, vec![uid]),
+                false => (uid, devs),
+            })
+            .collect()
+    }
+
+    // an unregistered id is owned by itself
+    pub fn owner(vec_did: &[Uid]) -> HashMap<Uid,Uid> {
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        vec_did.iter().for_each(|did| {
+            pipe.add_command(redis::cmd("GET").arg(owner_key(did)).to_owned());
+        });
+        let result: Vec<Option<Uid>> = pipe.query(&mut conn).unwrap();
+        vec_did.iter().copied().zip(result)
+            .map(|(did, uid)| (did, uid.unwrap_or(did)))
+            .collect()
+    }
+}
+
+// Group membership is kept beside the neighbor sets in db_nbr, where group ids share the user id space.
+pub mod db_grp {
+    extern crate redis;
+
+    use std::collections::HashMap;
+
+    use crate::db::db_nbr::get_redis_conn;
+    use crate::message::messaging::Uid;
+
+    pub(crate) const GROUP_SET: &str = "groups";
+    pub(crate) const MEMBERS_PREFIX: &str = "grp:";
+    pub(crate) const HISTORY_PREFIX: &str = "grp_hist:";
+
+    // (is_join, uid, timestamp)
+    pub type MemberEvent = (bool, Uid, u64);
+
+    fn members_key(gid: &Uid) -> String {
+        format!("{}{}", MEMBERS_PREFIX, gid)
+    }
+
+    pub(crate) fn history_key(gid: &Uid) -> String {
+        format!("{}{}", HISTORY_PREFIX, gid)
+    }
+
+    fn parse_event(event: &str) -> Option<MemberEvent> {
+        let mut items = event.split(',');
+        let is_join = items.next()? == "join";
+        Some((is_join, items.next()?.parse().ok()?, items.next()?.parse().ok()?))
+    }
+
+    pub(crate) fn parse_history(events: &[String]) -> Vec<MemberEvent> {
+        events.iter().filter_map(|event| parse_event(event)).collect()
+    }
+
+    // members are linked to the group in db_nbr, so the group shows up as one of their neighbors
+    pub fn add_members(gid: &Uid, members: &[Uid], ts: &u64) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        pipe.add_command(redis::cmd("SADD").arg(GROUP_SET).arg(gid).to_owned());
+        for uid in members {
+            pipe.add_command(redis::cmd("SADD").arg(members_key(gid)).arg(uid).to_owned());
+            pipe.add_command(redis::cmd("SADD").arg(uid).arg(gid).to_owned());
+            pipe.add_command(redis::cmd("RPUSH").arg(history_key(gid)).arg(format!("join,{},{}", uid, ts)).to_owned());
+        }
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    // leaving members stay in the member set, since they may have received earlier messages
+    pub fn remove_members(gid: &Uid, members: &[Uid], ts: &u64) -> redis::RedisResult<()> {
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        for uid in members {
+            pipe.add_command(redis::cmd("RPUSH").arg(history_key(gid)).arg(format!("leave,{},{}", uid, ts)).to_owned());
+        }
+        let _ : () = pipe.query(&mut conn)?;
+        Ok(())
+    }
+
+    // the history of every id that is a group, found in the same pipeline that reads the histories
+    pub fn histories(vec_id: &[Uid]) -> HashMap<Uid,Vec<MemberEvent>> {
+        if vec_id.is_empty() {
+            return HashMap::new();
+        }
+        let mut conn = get_redis_conn().unwrap();
+        let mut pipe = redis::Pipeline::new();
+
+        pipe.add_command(redis::cmd("SMISMEMBER").arg(GROUP_SET).arg(vec_id).to_owned());
+        vec_id.iter().for_each(|id| {
+            pipe.add_command(redis::cmd("LRANGE").arg(history_key(id)).arg(0).arg(-1).to_owned());
+        });
+        let mut result: Vec<redis::Value> = pipe.query(&mut conn).unwrap();
+        let is_group: Vec<bool> = redis::from_redis_value(result.remove(0)).unwrap();
+        vec_id.iter().copied().zip(is_group).zip(result)
+            .filter(|((_, is_group), _)| *is_group)
+            .map(|((gid, _), events)| (gid, parse_history(&redis::from_redis_value::<Vec<String>>(events).unwrap())))
+            .collect()
+    }
+
+    // membership events in the order they were recorded
+    pub fn history(gid: &Uid) -> Vec<MemberEvent> {
+        let mut conn = get_redis_conn().unwrap();
+        let events: Vec<String> = redis::cmd("LRANGE").arg(history_key(gid)).arg(0).arg(-1).query(&mut conn).unwrap();
+        parse_history(&events)
+    }
+
+    pub fn members_at(gid: &Uid, ts: &u64) -> Vec<Uid> {
+        members_in(&history(gid), ts)
+    }
+
+    // the members at ts, replayed from a history
+    pub fn members_in(history: &[MemberEvent], ts: &u64) -> Vec<Uid> {
+        let mut members: Vec<Uid> = Vec::new();
+        history.iter()
+            .filter(|(_, _, t)| t <= ts)
+            .for_each(|(is_join, uid, _)| {
+                members.retain(|m| m != uid);
+                is_join.then(|| members.push(*uid));
+            });
+        members
+    }
+
+    // every user that has ever been a member
+    pub fn ever_members(history: &[MemberEvent]) -> Vec<Uid> {
+        let mut members: Vec<Uid> = history.iter().map(|(_, uid, _)| *uid).collect();
+        members.sort();
+        members.dedup();
+        members
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate base64;
@@ -210,13 +661,15 @@ pub mod tests {
     use base64::encode;
     use rand::random;
     use test::Bencher;
-    use crate::db::{db_tag, db_nbr, db_ik};
-    use crate::message::messaging::{Edge, IdKey};
+    use crate::db::{db_tag, db_nbr, db_ik, db_grp, db_audit};
+    use crate::message::messaging::{Edge, IdKey, Uid};
+    use crate::message::sealed_sender::TraceAuthority;
 
     #[test]
     fn redis_is_open() {
         assert!(db_nbr::get_redis_conn().is_ok());
         assert!(db_ik::get_redis_conn().is_ok());
+        assert!(db_audit::get_redis_conn().is_ok());
         assert!(db_tag::get_set_conn().is_ok());
     }
 
@@ -274,15 +727,19 @@ pub mod tests {
     fn db_ik_add_query() {
         let mut vec_id_key = Vec::new();
         for _i in 0..1000 {
-            let id_key = IdKey::rand_key_gen(random::<u32>());
+            let id_key = IdKey::rand_key_gen(random::<Uid>(), &mut rand::thread_rng());
             vec_id_key.push(id_key);
         }
-        db_ik::add(&vec_id_key).ok();
-        let map_id_key = db_ik::query(&vec_id_key.iter().map(|x| x.id).collect());
+        let ta = TraceAuthority::new();
+        db_ik::add(&vec_id_key, &ta.public).ok();
+        let mut vec_id: Vec<Uid> = vec_id_key.iter().map(|x| x.id).collect();
+        vec_id.push(random::<Uid>());
+        let map_id_sealed = db_ik::query_sealed(&vec_id);
+        assert_eq!(map_id_sealed.len(), vec_id_key.len());
         for i in 0..vec_id_key.len() {
             let uid = vec_id_key.get(i).unwrap().id;
             let ukey = vec_id_key.get(i).unwrap().key;
-            assert_eq!(*map_id_key.get(&uid).unwrap(), ukey);
+            assert_eq!(ta.open_key(map_id_sealed.get(&uid).unwrap()), Some(ukey));
         }
         db_ik::clear();
     }
@@ -291,20 +748,64 @@ pub mod tests {
     fn db_nbr_add_query() {
         let mut vec_sess = Vec::<Edge>::new();
         for _i in 0..1000 {
-            vec_sess.push(Edge { sid: random::<u32>(), rid: random::<u32>() })
+            vec_sess.push(Edge::new(&random::<Uid>(), &random::<Uid>()))
         }
         db_nbr::add(&mut vec_sess).ok().unwrap();
         let _ = db_nbr::query(&vec_sess.iter().map(|x| x.sid).collect());
         db_nbr::clear();
     }
 
+    #[test]
+    fn db_grp_membership_history() {
+        let gid = random::<Uid>();
+        let [u1, u2, u3, u4] = [1, 2, 3, 4].map(Uid);
+        db_grp::add_members(&gid, &[u1, u2, u3], &10).unwrap();
+        db_grp::remove_members(&gid, &[u2], &20).unwrap();
+        db_grp::add_members(&gid, &[u4], &30).unwrap();
+
+        let map_gid_hist = db_grp::histories(&[gid, u1]);
+        assert_eq!(map_gid_hist.keys().collect::<Vec<&Uid>>(), vec![&gid]);
+        assert_eq!(db_grp::ever_members(&map_gid_hist[&gid]), vec![u1, u2, u3, u4]);
+        assert_eq!(db_grp::members_in(&map_gid_hist[&gid], &5), Vec::<Uid>::new());
+        assert_eq!(db_grp::members_at(&gid, &25), vec![u1, u3]);
+        assert_eq!(db_grp::members_at(&gid, &30), vec![u1, u3, u4]);
+        db_nbr::clear();
+    }
+
+    #[test]
+    fn db_nbr_query_frontier() {
+        let (uid, nbr, gid) = (random::<Uid>(), random::<Uid>(), random::<Uid>());
+        let keys: Vec<IdKey> = [uid, nbr, gid].iter().map(|id| IdKey::rand_key_gen(*id, &mut rand::thread_rng())).collect();
+        let ta = TraceAuthority::new();
+        db_ik::add(&keys, &ta.public).unwrap();
+        db_nbr::add(&vec![Edge::new(&uid, &nbr)]).unwrap();
+        db_grp::add_members(&gid, &[uid, nbr], &10).unwrap();
+
+        // only the sealed keys are mirrored
+        let (ik, nbrs) = db_nbr::query_frontier(&[uid]).unwrap().remove(0);
+        let mut nbrs: Vec<(Uid, Option<[u8; 16]>, Option<Vec<Uid>>)> = nbrs.into_iter()
+            .map(|(nbr, nbr_ik, history)| (nbr, ta.open_key(&nbr_ik.unwrap()), history.map(|h| db_grp::members_in(&h, &10))))
+            .collect();
+        nbrs.sort();
+        let mut expected = vec![(nbr, Some(keys[1].key), None), (gid, Some(keys[2].key), Some(vec![uid, nbr]))];
+        expected.sort();
+        assert_eq!(ta.open_key(&ik.unwrap()), Some(keys[0].key));
+        assert_eq!(nbrs, expected);
+
+        // clearing db_ik takes the mirror along
+        db_ik::clear();
+        let (ik, nbrs) = db_nbr::query_frontier(&[uid]).unwrap().remove(0);
+        assert!(ik.is_none() & nbrs.iter().all(|(_, nbr_ik, _)| nbr_ik.is_none()));
+        db_nbr::clear();
+    }
+
     #[test]
     fn test_db_ik_query() {
-        let id = random::<u32>();
-        let id_key = IdKey::rand_key_gen(id);
-        db_ik::add(&vec![id_key]).ok();
+        let id = random::<Uid>();
+        let id_key = IdKey::rand_key_gen(id, &mut rand::thread_rng());
+        db_ik::add(&vec![id_key], &TraceAuthority::new().public).ok();
         let start = std::time::Instant::now();
-        db_ik::query(&vec![id]);
+        db_ik::query_sealed(&vec![id]);
         let end = std::time::Instant::now();
         println!("Query runtime: {:?}", end - start);
     }
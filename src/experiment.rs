@@ -0,0 +1,517 @@
+#![allow(dead_code)]
+
+// The utility and privacy experiment: independent trials of spread, fuzzy traceback, membership values,
+// influence labels and metrics, run on a pool of threads and averaged in memory.
+pub mod runner {
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::{Arc, Mutex, atomic::{AtomicUsize, Ordering}};
+    use std::{fs, io, thread};
+
+    use petgraph::prelude::UnGraph;
+    use serde::Serialize;
+
+    use super::spec::Spec;
+    use crate::analysis::traceability::{records_to_map, inf_dist, traceability, correctness, privacy};
+    use crate::rwc_eval::rwc_eval::{select_dataset, dataset_model, Dataset};
+    use crate::simulation::{spread::Model, temporal::{TemporalGraph, import_temporal_graph}, influence::Influence, utils::{import_graph, vec_to_graph, SimSeed}, fuzzy_traceback::{any_leaf, fuzzy_trace_ours, calc_fuz_val}};
+
+    // spreads drawn per trial before it fails, e.g. when min_edges is out of the model's reach
+    pub const MAX_DRAWS: usize = 1000;
+
+    #[derive(Clone, Debug)]
+    pub struct Experiment {
+        pub graph_file: String,
+        pub st_node: usize,
+        pub round: usize,
+        pub model: Model,
+        // the contacts a temporal model spreads over, read from graph_file by run() if unset
+        pub contacts: Option<Arc<TemporalGraph>>,
+        pub trials: usize,
+        pub threads: usize,
+        pub trace_fpr: f32,
+        // forward graphs with fewer edges are drawn again
+        pub min_edges: usize,
+        pub metric: Influence,
+        // membership thresholds for traceability and correctness, ranges of membership values for privacy
+        pub thd_list: Vec<f64>,
+        pub range_list: Vec<f64>,
+        pub out_dir: String,
+    }
+
+    impl Default for Experiment {
+        fn default() -> Self {
+            Experiment::for_dataset(&Dataset::CollegeIM)
+        }
+    }
+
+    impl Experiment {
+        pub fn for_dataset(dataset: &Dataset) -> Self {
+            let (graph_file, st_node, round, _, _) = select_dataset(dataset);
+            Experiment {
+                graph_file,
+                st_node,
+                round,
+                model: dataset_model(dataset),
+                contacts: None,
+                trials: 50,
+                threads: thread::available_parallelism().map_or(1, |n| n.get()),
+                trace_fpr: 0.01,
+                min_edges: 200,
+                metric: Influence::KShell,
+                thd_list: vec![99.99, 99.995, 99.9995, 99.99995, 99.999995, 99.9999995, 99.99999995, 99.999999995, 100.0],
+                range_list: vec![0.0, 80.0, 90.0, 95.0, 99.0, 99.5, 99.9, 99.99, 100.0],
+                out_dir: "python/outputs".to_string(),
+            }
+        }
+    }
+
+    // the metric tables of one trial, one row per threshold, range or influence level
+    #[derive(Clone, Debug, Serialize)]
+    pub struct TrialResult {
+        pub trial: u64,
+        pub fwd_nodes: usize,
+        pub fwd_edges: usize,
+        pub traced_nodes: usize,
+        pub tables: BTreeMap<String, Vec<Vec<f64>>>,
+    }
+
+    #[derive(Clone, Debug, Serialize)]
+    pub struct ExperimentResult {
+        pub seed: u64,
+        pub trials: Vec<TrialResult>,
+        // cell-wise means over the trials that have the cell
+        pub tables: BTreeMap<String, Vec<Vec<f64>>>,
+    }
+
+    impl Experiment {
+        pub fn run(&self, seed: &SimSeed) -> io::Result<ExperimentResult> {
+            if let (Model::Temporal(_), None) = (self.model, &self.contacts) {
+                let contacts = Arc::new(import_temporal_graph(self.graph_file.clone()));
+                return Experiment { contacts: Some(contacts), ..self.clone() }.run(seed);
+            }
+            let sys_graph = self.contacts.as_ref().map_or_else(|| import_graph(self.graph_file.clone()), |contacts| contacts.static_graph());
+            self.run_on(&sys_graph, seed)
+        }
+
+        // trials are numbered from 1, as SIM_TRIAL was, and the first trial that fails stops the others
+        pub fn run_on(&self, sys_graph: &UnGraph<usize, ()>, seed: &SimSeed) -> io::Result<ExperimentResult> {
+            let next = AtomicUsize::new(1);
+            let results: Mutex<Vec<io::Result<TrialResult>>> = Mutex::new(Vec::new());
+            thread::scope(|scope| {
+                for _worker in 0..self.threads.max(1) {
+                    scope.spawn(|| loop {
+                        let trial = next.fetch_add(1, Ordering::SeqCst);
+                        if trial > self.trials {
+                            break;
+                        }
+                        let result = self.trial(sys_graph, &seed.trial(trial as u64));
+                        if result.is_err() {
+                            next.fetch_max(self.trials + 1, Ordering::SeqCst);
+                        }
+                        results.lock().unwrap().push(result);
+                    });
+                }
+            });
+            let mut trials = results.into_inner().unwrap().into_iter().collect::<io::Result<Vec<TrialResult>>>()?;
+            trials.sort_by_key(|t| t.trial);
+            let tables = mean_tables(&trials);
+            Ok(ExperimentResult { seed: seed.seed, trials, tables })
+        }
+
+        // fails when none of MAX_DRAWS spreads has min_edges edges and a leaf to report from,
+        // or when a temporal model has no contacts to spread over
+        pub fn trial(&self, sys_graph: &UnGraph<usize, ()>, seed: &SimSeed) -> io::Result<TrialResult> {
+            let mut rng = seed.rng();
+            let Some(model) = self.model.over(self.contacts.as_deref()) else {
+                let error = format!("trial {}: the {:?} model spreads over the contacts of a temporal graph, and the experiment has none", seed.trial, self.model);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+            };
+            for _draw in 0..MAX_DRAWS {
+                // 1. spread
+                let (infected_edges, node_src) = model.spread(&self.round, &self.st_node, sys_graph, &mut rng);
+                if infected_edges.len() < self.min_edges {
+                    continue;
+                }
+                let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&infected_edges);
+                let Some(start_node) = any_leaf(&fwd_graph) else { continue };
+
+                // 2. fuzzy traceback and membership values
+                let (bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &self.trace_fpr, &mut rng);
+                let (mut memb_val, _) = calc_fuz_val(&(self.trace_fpr as f64), &max_depth, &start_node, &bwd_traced_edges, &mut fwd_traced_edges, sys_graph);
+                // the reporter, as test_fuzz_ours records it
+                memb_val.insert(start_node, 0.85);
+
+                // 3. influence labels and metrics
+                let val_list = records_to_map(&self.metric.records(&memb_val, &fwd_graph));
+                let tables = BTreeMap::from([
+                    ("influence".to_string(), inf_dist(&val_list).iter().map(|(i, count)| vec![*i as f64, *count as f64]).collect()),
+                    ("correct".to_string(), traceability(&val_list, &self.thd_list).iter().map(|(fp, all, fpr)| vec![*fp as f64, *all as f64, *fpr]).collect()),
+                    ("detect".to_string(), correctness(&val_list, &self.thd_list)),
+                    ("priv".to_string(), privacy(&val_list, &self.range_list).iter().map(|(fp, all, fpr)| vec![*fp as f64, *all as f64, *fpr]).collect()),
+                ]);
+                return Ok(TrialResult { trial: seed.trial, fwd_nodes: fwd_graph.node_count(), fwd_edges: infected_edges.len(), traced_nodes: memb_val.len(), tables });
+            }
+            let error = format!("trial {}: no spread of {:?} from {} reached {} edges with a leaf in {} draws", seed.trial, self.model, self.st_node, self.min_edges, MAX_DRAWS);
+            Err(io::Error::new(io::ErrorKind::InvalidInput, error))
+        }
+    }
+
+    // every sweep point of a utility spec on one graph, each written to its out_dir
+    pub fn run_spec(spec: &Spec, seed: &SimSeed) -> io::Result<Vec<ExperimentResult>> {
+        let (sys_graph, contacts) = spec.load_graph()?;
+        let mut results = Vec::new();
+        for experiment in spec.experiments()? {
+            let experiment = Experiment { contacts: contacts.clone(), ..experiment };
+            let result = experiment.run_on(&sys_graph, seed)?;
+            result.write(&experiment.out_dir)?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    // rows and columns may differ between trials, e.g. the number of shells, so each cell is averaged over
+    // the trials that have it, skipping undefined rates
+    fn mean_tables(trials: &[TrialResult]) -> BTreeMap<String, Vec<Vec<f64>>> {
+        let mut sums: BTreeMap<String, Vec<Vec<(f64, usize)>>> = BTreeMap::new();
+        for trial in trials {
+            for (name, table) in &trial.tables {
+                let sum = sums.entry(name.clone()).or_default();
+                for (i, row) in table.iter().enumerate() {
+                    (sum.len() <= i).then(|| sum.push(Vec::new()));
+                    for (j, val) in row.iter().enumerate() {
+                        (sum[i].len() <= j).then(|| sum[i].push((0.0, 0)));
+                        if val.is_finite() {
+                            sum[i][j] = (sum[i][j].0 + val, sum[i][j].1 + 1);
+                        }
+                    }
+                }
+            }
+        }
+        sums.into_iter().map(|(name, table)| {
+            let mean = table.into_iter().map(|row| row.into_iter().map(|(sum, count)| sum / count as f64).collect()).collect();
+            (name, mean)
+        }).collect()
+    }
+
+    impl ExperimentResult {
+        // one <table>.csv per table and experiment.json with everything
+        pub fn write(&self, out_dir: &String) -> io::Result<()> {
+            fs::create_dir_all(out_dir)?;
+            for (name, table) in &self.tables {
+                let mut csv = format!("# seed: {}, trials: {}\n", self.seed, self.trials.len());
+                table.iter().for_each(|row| {
+                    csv += &row.iter().map(|val| val.to_string()).collect::<Vec<String>>().join(",");
+                    csv += "\n";
+                });
+                fs::write(format!("{}/{}.csv", out_dir, name), csv)?;
+            }
+            fs::write(format!("{}/experiment.json", out_dir), serde_json::to_string_pretty(self).unwrap())
+        }
+
+        pub fn summary(&self) -> HashMap<&str, f64> {
+            let mean = |f: fn(&TrialResult) -> usize| self.trials.iter().map(|t| f(t) as f64).sum::<f64>() / self.trials.len() as f64;
+            HashMap::from([("fwd_nodes", mean(|t| t.fwd_nodes)), ("fwd_edges", mean(|t| t.fwd_edges)), ("traced_nodes", mean(|t| t.traced_nodes))])
+        }
+    }
+}
+
+// Experiment specs, read from TOML or JSON so that a new study needs no recompile, see experiments/.
+// Every field is optional, the defaults are those of runner::Experiment::default().
+pub mod spec {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::{fs, io, path::Path};
+
+    use petgraph::prelude::UnGraph;
+    use serde::{Deserialize, Serialize};
+
+    use super::runner::Experiment;
+    use crate::rwc_eval::rwc_eval::{select_dataset, dataset_model, Dataset};
+    use crate::simulation::{spread::Model, temporal::{TemporalGraph, import_temporal_graph}, influence::Influence, utils::{import_graph, SimSeed}};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Study {
+        // traceability, correctness and privacy of the membership values, run by runner::run_spec
+        #[default]
+        Utility,
+        // fuzzy traceback runtime and lookup latency on redis, run by rwc_eval::eval_spec
+        TraceTime,
+        LookupLatency,
+    }
+
+    // a bundled dataset, or a graph file with its start node and rounds, which also override the dataset's
+    #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct GraphSpec {
+        pub dataset: Option<Dataset>,
+        pub file: Option<String>,
+        pub st_node: Option<usize>,
+        pub round: Option<usize>,
+        // the file holds (source, destination, time) contacts, as a temporal dataset's does
+        #[serde(default)]
+        pub temporal: bool,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct Spec {
+        pub study: Study,
+        pub graph: GraphSpec,
+        // the dataset's SIR model if unset, or its temporal one for a temporal dataset
+        pub model: Option<Model>,
+        // each table is a grid over model parameters, e.g. [[sweep]] s2i = [0.03, 0.04] and i2r = [0.7]
+        pub sweep: Vec<BTreeMap<String, Vec<f64>>>,
+        pub trace_fpr: f32,
+        pub trials: usize,
+        // all cores if unset
+        pub threads: Option<usize>,
+        // SIM_SEED if unset
+        pub seed: Option<u64>,
+        pub min_edges: usize,
+        pub influence: Influence,
+        pub thresholds: Vec<f64>,
+        pub ranges: Vec<f64>,
+        pub out_dir: String,
+    }
+
+    impl Default for Spec {
+        fn default() -> Self {
+            let experiment = Experiment::default();
+            Spec {
+                study: Study::Utility,
+                graph: GraphSpec { dataset: Some(Dataset::CollegeIM), ..GraphSpec::default() },
+                model: None,
+                sweep: Vec::new(),
+                trace_fpr: experiment.trace_fpr,
+                trials: experiment.trials,
+                threads: None,
+                seed: None,
+                min_edges: experiment.min_edges,
+                influence: experiment.metric,
+                thresholds: experiment.thd_list,
+                ranges: experiment.range_list,
+                out_dir: experiment.out_dir,
+            }
+        }
+    }
+
+    pub type LoadedGraph = (UnGraph<usize, ()>, Option<Arc<TemporalGraph>>);
+
+    fn invalid<E: ToString>(err: E) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+    }
+
+    impl Spec {
+        // JSON for .json files, TOML otherwise
+        pub fn load(path: &str) -> io::Result<Spec> {
+            let text = fs::read_to_string(path)?;
+            match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+                Some("json") => Ok(serde_json::from_str(&text)?),
+                _ => toml::from_str(&text).map_err(invalid),
+            }
+        }
+
+        // (graph file, start node, rounds)
+        pub fn graph(&self) -> io::Result<(String, usize, usize)> {
+            let preset = self.graph.dataset.map(|dataset| select_dataset(&dataset));
+            let file = self.graph.file.clone().or(preset.as_ref().map(|p| p.0.clone()));
+            let st_node = self.graph.st_node.or(preset.as_ref().map(|p| p.1));
+            let round = self.graph.round.or(preset.as_ref().map(|p| p.2));
+            match (file, st_node, round) {
+                (Some(file), Some(st_node), Some(round)) => Ok((file, st_node, round)),
+                _ => Err(invalid("graph needs a dataset, or a file, st_node and round")),
+            }
+        }
+
+        // an experiment without trials has nothing to average
+        pub fn trials(&self) -> io::Result<usize> {
+            match self.trials {
+                0 => Err(invalid("trials must be at least 1")),
+                trials => Ok(trials),
+            }
+        }
+
+        pub fn is_temporal(&self) -> bool {
+            self.graph.temporal | self.graph.dataset.is_some_and(|dataset| dataset.is_temporal())
+        }
+
+        // the system graph, and the contacts it is the static graph of when the graph is temporal
+        pub fn load_graph(&self) -> io::Result<LoadedGraph> {
+            let (file, _, _) = self.graph()?;
+            if !self.is_temporal() {
+                return Ok((import_graph(file), None));
+            }
+            let contacts = import_temporal_graph(file);
+            Ok((contacts.static_graph(), Some(Arc::new(contacts))))
+        }
+
+        // the model at every sweep point, grids in order and parameters in name order within a grid
+        pub fn models(&self) -> io::Result<Vec<Model>> {
+            let base = match (self.model, self.graph.dataset) {
+                (Some(model), _) => model,
+                (None, Some(dataset)) => dataset_model(&dataset),
+                (None, None) => return Err(invalid("model is needed for a graph file")),
+            };
+            if matches!(base, Model::Temporal(_)) & !self.is_temporal() {
+                return Err(invalid("a temporal model needs a temporal graph"));
+            }
+            if self.sweep.is_empty() {
+                return Ok(vec![base]);
+            }
+            let mut models = Vec::new();
+            for grid in &self.sweep {
+                let mut points: Vec<Vec<(&String, f64)>> = vec![Vec::new()];
+                for (name, vals) in grid {
+                    points = points.into_iter().flat_map(|point| vals.iter().map(move |val| [point.clone(), vec![(name, *val)]].concat())).collect();
+                }
+                for point in points {
+                    models.push(with_params(&base, &point)?);
+                }
+            }
+            Ok(models)
+        }
+
+        // one utility experiment per sweep point, written to out_dir/<point> when there are several
+        pub fn experiments(&self) -> io::Result<Vec<Experiment>> {
+            let (graph_file, st_node, round) = self.graph()?;
+            let trials = self.trials()?;
+            let models = self.models()?;
+            let default = Experiment::default();
+            Ok(models.iter().enumerate().map(|(i, model)| Experiment {
+                graph_file: graph_file.clone(),
+                st_node,
+                round,
+                model: *model,
+                contacts: None,
+                trials,
+                threads: self.threads.unwrap_or(default.threads),
+                trace_fpr: self.trace_fpr,
+                min_edges: self.min_edges,
+                metric: self.influence,
+                thd_list: self.thresholds.clone(),
+                range_list: self.ranges.clone(),
+                out_dir: match models.len() {
+                    1 => self.out_dir.clone(),
+                    _ => format!("{}/{}", self.out_dir, i),
+                },
+            }).collect())
+        }
+
+        pub fn sim_seed(&self) -> SimSeed {
+            self.seed.map_or_else(SimSeed::from_env, SimSeed::new)
+        }
+    }
+
+    // overrides the named parameters of a model, which must all exist in it
+    fn with_params(model: &Model, params: &[(&String, f64)]) -> io::Result<Model> {
+        let mut value = serde_json::to_value(model)?;
+        for (name, val) in params {
+            match value.get_mut(name.as_str()) {
+                // whole numbers stay integers, e.g. a temporal period
+                Some(field) if field.is_u64() => *field = serde_json::json!(*val as u64),
+                Some(field) if name.as_str() != "kind" => *field = serde_json::json!(val),
+                _ => return Err(invalid(format!("{:?} has no parameter {}", model, name))),
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::experiment::{runner::{Experiment, run_spec}, spec::{Spec, Study, GraphSpec}};
+    use crate::simulation::{generators, utils::SimSeed, spread::{Model, Sir, IndependentCascade}, temporal::TemporalRate, influence::Influence};
+
+    #[test]
+    fn test_experiment_runner() {
+        let sys_graph = generators::barabasi_albert(&500, &3, &SimSeed::new(1));
+        let experiment = Experiment { st_node: 0, trials: 4, threads: 2, min_edges: 50, ..Experiment::default() };
+        let seed = SimSeed::new(3);
+        let result = experiment.run_on(&sys_graph, &seed).unwrap();
+        assert_eq!(result.trials.iter().map(|t| t.trial).collect::<Vec<u64>>(), vec![1, 2, 3, 4]);
+        // the same trial gives the same tables whichever thread ran it
+        let single = Experiment { threads: 1, ..experiment.clone() }.run_on(&sys_graph, &seed).unwrap();
+        assert_eq!(serde_json::to_string(&result).unwrap(), serde_json::to_string(&single).unwrap());
+        assert_eq!(result.tables["correct"].len(), experiment.thd_list.len());
+        assert_eq!(result.tables["priv"].len(), experiment.range_list.len() - 1);
+        let trial = experiment.trial(&sys_graph, &seed.trial(2)).unwrap();
+        assert_eq!(trial.tables, result.trials[1].tables);
+
+        // a forward graph larger than the system graph is never drawn, so the trial fails instead of looping
+        let unreachable = Experiment { min_edges: sys_graph.edge_count() + 1, ..experiment };
+        let error = unreachable.run_on(&sys_graph, &seed).unwrap_err();
+        assert!(error.to_string().starts_with("trial "), "{}", error);
+    }
+
+    #[test]
+    fn test_spec() {
+        // the bundled utility spec is the default experiment
+        let spec = Spec::load("experiments/utility.toml").unwrap();
+        assert_eq!(spec.study, Study::Utility);
+        let experiment = &spec.experiments().unwrap()[0];
+        let default = Experiment::default();
+        assert_eq!((&experiment.graph_file, experiment.st_node, experiment.round, experiment.model), (&default.graph_file, default.st_node, default.round, default.model));
+        assert_eq!((&experiment.thd_list, &experiment.range_list, experiment.metric), (&default.thd_list, &default.range_list, default.metric));
+
+        // sweeps are grids over the model parameters, in name order
+        let spec = Spec::load("experiments/trace_time.toml").unwrap();
+        let models = spec.models().unwrap();
+        assert_eq!((models.len(), spec.min_edges, spec.graph().unwrap().2), (12, 50, 20));
+        assert_eq!(models[0], Model::Sir(Sir { s2i: 0.05, i2r: 0.4 }));
+        assert_eq!(models[11], Model::Sir(Sir { s2i: 0.08, i2r: 0.7 }));
+
+        // the same spec as JSON, with a graph file instead of a dataset
+        let json = r#"{"graph": {"file": "g.txt", "st_node": 3, "round": 5}, "model": {"kind": "independent_cascade", "p": 0.1}, "sweep": [{"p": [0.1, 0.2]}], "influence": "pagerank", "seed": 7}"#;
+        let spec: Spec = serde_json::from_str(json).unwrap();
+        assert_eq!(spec.graph().unwrap(), ("g.txt".to_string(), 3, 5));
+        assert_eq!(spec.sim_seed(), SimSeed::new(7));
+        let experiments = spec.experiments().unwrap();
+        assert_eq!(experiments.iter().map(|e| e.model).collect::<Vec<Model>>(), vec![Model::IndependentCascade(IndependentCascade { p: 0.1 }), Model::IndependentCascade(IndependentCascade { p: 0.2 })]);
+        assert_eq!((experiments[1].out_dir.as_str(), experiments[1].metric, experiments[1].trials), ("python/outputs/1", Influence::PageRank, 50));
+
+        // unknown parameters, fields and incomplete graphs are errors
+        assert!(serde_json::from_str::<Spec>(r#"{"model": {"kind": "sir", "s2i": 0.1, "i2r": 0.5}, "sweep": [{"p": [0.1]}]}"#).unwrap().models().is_err());
+        assert!(serde_json::from_str::<Spec>(r#"{"trial": 3}"#).is_err());
+        assert!(serde_json::from_str::<Spec>(r#"{"graph": {"file": "g.txt"}}"#).unwrap().graph().is_err());
+        assert!(serde_json::from_str::<Spec>(r#"{"trials": 0}"#).unwrap().experiments().is_err());
+    }
+
+    #[test]
+    fn test_temporal_spec() {
+        // every edge of a static graph as two contacts, well apart in time
+        let sys_graph = generators::barabasi_albert(&300, &3, &SimSeed::new(1));
+        let dir = std::env::temp_dir().join(format!("temporal_spec_{}", hex::encode(rand::random::<[u8; 8]>())));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("contacts.txt");
+        let contacts: String = sys_graph.edge_indices().enumerate().map(|(i, e)| {
+            let (u, v) = sys_graph.edge_endpoints(e).unwrap();
+            format!("{} {} {}\n{} {} {}\n", u.index(), v.index(), i * 7 % 3000, u.index(), v.index(), 5000 + i)
+        }).collect();
+        fs::write(&file, contacts).unwrap();
+
+        let spec = serde_json::json!({
+            "graph": {"file": file, "st_node": 0, "round": 20, "temporal": true},
+            "model": {"kind": "temporal", "s2i": 0.3, "period": 10000},
+            "sweep": [{"period": [3000, 10000]}],
+            "trials": 2, "threads": 2, "min_edges": 30, "seed": 5,
+            "out_dir": dir.join("out"),
+        });
+        let spec: Spec = serde_json::from_value(spec).unwrap();
+        assert_eq!(spec.models().unwrap()[0], Model::Temporal(TemporalRate { s2i: 0.3, period: 3000 }));
+        let results = run_spec(&spec, &spec.sim_seed()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().flat_map(|r| &r.trials).all(|t| (t.fwd_edges >= 30) & (t.traced_nodes > 0)));
+
+        // a temporal model cannot spread on a static graph, nor run without the contacts
+        let static_spec = Spec { graph: GraphSpec { temporal: false, ..spec.graph.clone() }, ..spec };
+        assert!(static_spec.models().is_err());
+        let experiment = Experiment { model: Model::Temporal(TemporalRate { s2i: 0.3, period: 3000 }), contacts: None, trials: 1, ..Experiment::default() };
+        let error = experiment.run_on(&sys_graph, &SimSeed::new(5)).unwrap_err();
+        assert!(error.to_string().starts_with("trial 1:"), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -7,7 +7,34 @@ mod trace;
 mod simulation;
 mod analysis;
 mod rwc_eval;
+mod warrant;
+mod audit;
+mod experiment;
 
+use experiment::{runner, spec::{Spec, Study}};
+use rwc_eval::rwc_eval::eval_spec;
+
+// `experiment [spec]` runs an experiment spec, experiments/utility.toml by default
 fn main() {
     println!("Impact Tracing: Identifying the Culprit of Misinformation for Encrypted Messaging Systems.");
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("experiment") {
+        let path = args.get(2).map_or("experiments/utility.toml", |path| path.as_str());
+        let spec = Spec::load(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+        // drawn once when the spec has no seed, so the header names the seed every study runs with
+        let seed = spec.sim_seed();
+        println!("{}, study: {:?}, trials: {}", seed.header(), spec.study, spec.trials);
+        match spec.study {
+            Study::Utility => {
+                for (experiment, result) in spec.experiments().unwrap().iter().zip(runner::run_spec(&spec, &seed).unwrap()) {
+                    println!("{:?}; {:?}, written to {}", experiment.model, result.summary(), experiment.out_dir);
+                }
+            }
+            Study::TraceTime | Study::LookupLatency => {
+                for (model, record) in eval_spec(&spec, &seed).unwrap() {
+                    println!("{:?}; {:?}", model, record);
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
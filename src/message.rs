@@ -5,37 +5,112 @@ pub mod messaging {
 
     use crate::tool::algos::*;
     use crate::db::{db_tag, db_ik};
-    use crate::tool::utils::{hash, encryption, decryption};
+    use crate::tool::utils::{hash, encryption, try_decryption};
+    use std::{fmt, num::ParseIntError, str::FromStr};
     use base64::encode;
+    use rand::distributions::{Distribution, Standard};
     use serde::{Serialize, Deserialize};
 
+    // Users, devices and groups share one id space, wide enough to hold UUIDs.
+    // Written as its decimal string in db keys and values, which is what FromStr reads back
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[serde(transparent)]
+    pub struct Uid(pub u128);
+    pub const UID_LEN: usize = std::mem::size_of::<Uid>();
+
+    impl Uid {
+        pub fn to_bytes(self) -> [u8; UID_LEN] {
+            self.0.to_le_bytes()
+        }
+        pub fn from_bytes(bytes: [u8; UID_LEN]) -> Uid {
+            Uid(u128::from_le_bytes(bytes))
+        }
+        // the node of a simulated graph, whose ids always fit an index
+        pub fn index(&self) -> usize {
+            usize::try_from(self.0).expect("uid is out of the index range of a simulated graph")
+        }
+    }
+
+    impl From<u64> for Uid {
+        fn from(id: u64) -> Uid {
+            Uid(id as u128)
+        }
+    }
+
+    impl From<usize> for Uid {
+        fn from(id: usize) -> Uid {
+            Uid(id as u128)
+        }
+    }
+
+    impl fmt::Display for Uid {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl FromStr for Uid {
+        type Err = ParseIntError;
+        fn from_str(s: &str) -> Result<Uid, ParseIntError> {
+            Ok(Uid(s.parse()?))
+        }
+    }
+
+    impl Distribution<Uid> for Standard {
+        fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Uid {
+            Uid(rng.gen())
+        }
+    }
+
+    impl redis::ToRedisArgs for Uid {
+        fn write_redis_args<W: ?Sized + redis::RedisWrite>(&self, out: &mut W) {
+            out.write_arg(self.to_string().as_bytes())
+        }
+    }
+
+    impl redis::FromRedisValue for Uid {
+        fn from_redis_value(v: redis::Value) -> Result<Uid, redis::ParsingError> {
+            let id: String = redis::from_redis_value(v)?;
+            id.parse().map_err(|_| format!("invalid uid {}", id).into())
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct Edge {
-        pub sid: u32,
-        pub rid: u32,
+        pub sid: Uid,
+        pub rid: Uid,
+        // set when the message was delivered to rid through a group send
+        #[serde(default)]
+        pub gid: Option<Uid>,
     }
 
     impl Edge {
-        pub fn new(sid: &u32, rid: &u32) -> Edge {
-            Edge { sid: *sid, rid: *rid }
+        pub fn new(sid: &Uid, rid: &Uid) -> Edge {
+            Edge { sid: *sid, rid: *rid, gid: None }
+        }
+        pub fn via_group(sid: &Uid, gid: &Uid, rid: &Uid) -> Edge {
+            Edge { sid: *sid, rid: *rid, gid: Some(*gid) }
         }
         pub fn show(&self) {
-            print!("U{} - U{}, ", self.sid, self.rid);
+            match self.gid {
+                Some(gid) => print!("U{} - G{} - U{}, ", self.sid, gid, self.rid),
+                None => print!("U{} - U{}, ", self.sid, self.rid),
+            }
         }
     }
 
     #[derive(Debug)]
     pub struct IdKey {
-        pub id: u32,
+        pub id: Uid,
         pub key: [u8; 16],
     }
 
     impl IdKey {
-        pub fn rand_key_gen(id: u32) -> IdKey {
-            let key = rand::random::<[u8; 16]>();
+        pub fn rand_key_gen<R: rand::Rng + ?Sized>(id: Uid, rng: &mut R) -> IdKey {
+            let key = rng.gen::<[u8; 16]>();
             IdKey { id, key }
         }
-        pub fn id_as_key_gen (id: u32) -> IdKey {
+        pub fn id_as_key_gen (id: Uid) -> IdKey {
             let key = hash(&(id).to_string());
             IdKey { id, key }
         }
@@ -51,6 +126,13 @@ pub mod messaging {
         pub p_tag: [u8; 32],
         pub ct_1: [u8; 32],
         pub ct_2: [u8; 16],
+        // all zero for a packet without a hop counter
+        #[serde(default)]
+        pub hop: u32,
+        #[serde(default)]
+        pub hop_proof: [u8; 16],
+        #[serde(default)]
+        pub hop_anchor: [u8; 16],
     }
 
     impl MsgPacket {
@@ -64,6 +146,9 @@ pub mod messaging {
                 p_tag: Default::default(),
                 ct_1: Default::default(),
                 ct_2: Default::default(),
+                hop: 0,
+                hop_proof: Default::default(),
+                hop_anchor: Default::default(),
             }
         }
         pub fn new_with_ek(tag_key: &[u8; 16], message: &String, prf: &[u8;32], ek: &[u8;16], ct: &[u8;48], p_tag: &[u8;32]) -> Self {
@@ -77,10 +162,65 @@ pub mod messaging {
                 // gen ct_1 and ct_2
                 ct_1: ct[..32].try_into().unwrap(),
                 ct_2: ct[32..].try_into().unwrap(),
+                hop: 0,
+                hop_proof: Default::default(),
+                hop_anchor: Default::default(),
+            }
+        }
+
+        pub fn has_hop_counter(&self) -> bool {
+            (self.hop, self.hop_proof, self.hop_anchor) != (0, [0; 16], [0; 16])
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum HopAction {
+        Deliver,
+        Label,
+        Block,
+    }
+
+    // label messages forwarded more than label_after times and stop them after block_after
+    #[derive(Debug, Clone)]
+    pub struct HopPolicy {
+        pub label_after: u32,
+        pub block_after: u32,
+    }
+
+    impl HopPolicy {
+        pub fn new(label_after: u32, block_after: u32) -> Self {
+            HopPolicy { label_after, block_after }
+        }
+        pub fn check(&self, hop: &u32) -> HopAction {
+            if *hop > self.block_after {
+                HopAction::Block
+            } else if *hop > self.label_after {
+                HopAction::Label
+            } else {
+                HopAction::Deliver
             }
         }
     }
 
+    impl Default for HopPolicy {
+        fn default() -> Self {
+            HopPolicy::new(5, MAX_HOP)
+        }
+    }
+
+    // A bundle of parts (e.g. text + images), where each part keeps its own tag key chain
+    #[derive(Serialize,Deserialize,Debug)]
+    pub struct MultiMsgPacket {
+        pub parts: Vec<MsgPacket>,
+    }
+
+    impl MultiMsgPacket {
+        // None if the bundle has no part at index
+        pub fn part(&self, index: usize) -> Option<&MsgPacket> {
+            self.parts.get(index)
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug)]
     pub struct MsgReport {
         pub key: [u8; 16],
@@ -99,16 +239,70 @@ pub mod messaging {
         let ct: [u8; 48] = encryption(&ek, &t);
         let hk: [u8; 16] = hk_gen(&tk);
         let p_tag = tag_proc(&t, &hk);
-        MsgPacket::new_with_ek(&tag_key, message, &t, &ek, &ct, &p_tag)
+        let mut packet = MsgPacket::new_with_ek(&tag_key, message, &t, &ek, &ct, &p_tag);
+        // a new message starts a hop counter at 0, a forward gets the previous one from forward_packet
+        if prev_key == &[0; 16] {
+            (packet.hop_proof, packet.hop_anchor) = hop_counter_gen();
+        }
+        packet
+    }
+
+    // forward a received packet, continuing both its tag key chain and its hop counter
+    pub fn forward_packet(prev_packet: &MsgPacket, tk: &[u8; 16]) -> MsgPacket {
+        let mut packet = send_packet(&prev_packet.payload, &prev_packet.tag_key, tk);
+        if !prev_packet.has_hop_counter() {
+            return packet;
+        }
+        packet.hop_anchor = prev_packet.hop_anchor;
+        (packet.hop, packet.hop_proof) = match prev_packet.hop < MAX_HOP {
+            true => (prev_packet.hop + 1, hop_next(&prev_packet.hop_proof)),
+            false => (prev_packet.hop, prev_packet.hop_proof),
+        };
+        packet
     }
 
-    // proc_msg:
-    pub fn plt_proc_packet(sess: &Edge, packet: &mut MsgPacket) {
-        let map_id_key = db_ik::query(&vec![sess.sid]);
-        let ik = map_id_key.get(&sess.sid).unwrap();
-        let tk = tk_gen(ik, &sess.rid);
-        let hk = hk_gen(&tk);
-        packet.hk = hk;
+    // client hook: refuse to forward once the policy blocks the next hop
+    pub fn client_forward(policy: &HopPolicy, prev_packet: &MsgPacket, tk: &[u8; 16]) -> Option<MsgPacket> {
+        (policy.check(&(prev_packet.hop + 1)) != HopAction::Block).then(|| forward_packet(prev_packet, tk))
+    }
+
+    // Platform hook: a packet with a forged counter is blocked like one beyond the limit.
+    // The anchor is bound to the tag of the hop that started the counter, so a counter
+    // made up by a forwarder can only pass as a new message, never as a later hop of one.
+    // A packet without a counter may have been forwarded any number of times and is labelled.
+    pub fn plt_check_hop(policy: &HopPolicy, packet: &MsgPacket) -> HopAction {
+        if !packet.has_hop_counter() {
+            return HopAction::Label;
+        }
+        if !hop_verify(&packet.hop, &packet.hop_proof, &packet.hop_anchor) {
+            return HopAction::Block;
+        }
+        let anchor = encode(packet.hop_anchor);
+        let bound = match packet.hop {
+            0 => db_tag::bind_anchor(&anchor, &encode(packet.p_tag)),
+            _ => db_tag::anchor_bound(&anchor),
+        };
+        match bound {
+            true => policy.check(&packet.hop),
+            false => HopAction::Block,
+        }
+    }
+
+    // The sender registers the hk of a session when opening it, so the platform
+    // processes its messages without ever holding the identity key.
+    pub fn open_session(sess: &Edge, ik: &[u8; 16]) -> redis::RedisResult<()> {
+        db_ik::add_hk(&[(sess.clone(), hk_gen(&tk_gen(ik, &sess.rid)))])
+    }
+
+    // proc_msg: false for a session that was never opened
+    pub fn plt_proc_packet(sess: &Edge, packet: &mut MsgPacket) -> bool {
+        match db_ik::query_hk(sess) {
+            Some(hk) => {
+                packet.hk = hk;
+                true
+            },
+            None => false,
+        }
     }
 
     pub fn store_tag(packet: &mut MsgPacket) {
@@ -125,9 +319,9 @@ pub mod messaging {
         let (one, two) = ct.split_at_mut(packet.ct_1.len());
         one.copy_from_slice(&packet.ct_1);
         two.copy_from_slice(&packet.ct_2);
-        let tag = decryption(&packet.epheral_key, &ct);
-        // 3. Verify tag
-        prf == tag
+        let tag = try_decryption(&packet.epheral_key, &ct);
+        // 3. Verify tag and hop counter, if it has one
+        (Some(prf) == tag) & (!packet.has_hop_counter() || hop_verify(&packet.hop, &packet.hop_proof, &packet.hop_anchor))
     }
 
     // report_msg:
@@ -135,16 +329,271 @@ pub mod messaging {
         (MsgReport { key: *tag_key, payload: message.clone()}, sess.clone())
     }
 
+    // the tag of a single hop is checked with the session's hk
     pub fn verify_report(sess: &Edge, report: &MsgReport) -> bool {
-        let map_id_key = db_ik::query(&vec![sess.sid]);
-        let ik = map_id_key.get(&sess.sid).unwrap();
-        let tk = tk_gen(ik, &sess.rid);
-        tag_exists( &report.key, &tk, &report.payload)
+        match db_ik::query_hk(sess) {
+            Some(hk) => db_tag::exists(&encode(tag_proc(&prf_gen(&report.key, &report.payload), &hk))),
+            None => false,
+        }
     }
-    
+
+// Multi-device messages
+// -------------------------------------------------------------------------------------------------
+
+    // a send to a user is delivered to each of the user's devices under its own tracing key, all chained from one
+    // previous key, so a new message draws that key and its hop counter once for every device
+    pub fn send_packet_to_devices(message: &String, prev_key: &[u8; 16], sdik: &[u8; 16], rcv_devices: &[Uid]) -> Vec<(Uid, MsgPacket)> {
+        let hop_counter = (prev_key == &[0; 16]).then(hop_counter_gen);
+        let prev_key = match hop_counter {
+            Some(_) => rand::random::<[u8; 16]>(),
+            None => *prev_key,
+        };
+        rcv_devices.iter()
+            .map(|rdid| {
+                let mut packet = send_packet(message, &prev_key, &tk_gen(sdik, rdid));
+                if let Some((proof, anchor)) = hop_counter {
+                    (packet.hop_proof, packet.hop_anchor) = (proof, anchor);
+                }
+                (*rdid, packet)
+            })
+            .collect()
+    }
+
+// Group messages
+// -------------------------------------------------------------------------------------------------
+
+    // a member's session with the group, registered by whoever holds the group's identity key
+    pub fn open_group_session(sid: &Uid, gid: &Uid, gik: &[u8; 16]) -> redis::RedisResult<()> {
+        db_ik::add_hk(&[(Edge::new(sid, gid), hk_gen(&group_tk_gen(gik, sid)))])
+    }
+
+    // a group send is processed once and yields a single tag for all members
+    pub fn plt_proc_group_packet(sid: &Uid, gid: &Uid, packet: &mut MsgPacket) -> bool {
+        plt_proc_packet(&Edge::new(sid, gid), packet)
+    }
+
+    // stored with the time of the send, which fixes the members the tag reached
+    pub fn store_group_tag(packet: &MsgPacket, ts: &u64) {
+        let _ = db_tag::add_timed(&[(encode(packet.p_tag), *ts)]);
+    }
+
+    pub fn verify_group_report(sid: &Uid, gid: &Uid, report: &MsgReport) -> bool {
+        verify_report(&Edge::new(sid, gid), report)
+    }
+
+// Multi-part messages
+// -------------------------------------------------------------------------------------------------
+
+    // each part is (payload, prev_key), where a zero prev_key starts a new chain for that part
+    pub fn send_multi_packet(parts: &[(String, [u8; 16])], tk: &[u8; 16]) -> MultiMsgPacket {
+        let packets = parts.iter()
+            .map(|(message, prev_key)| send_packet(message, prev_key, tk))
+            .collect();
+        MultiMsgPacket { parts: packets }
+    }
+
+    // forward a single part of a received bundle on its own
+    pub fn forward_part(packet: &MultiMsgPacket, index: usize, tk: &[u8; 16]) -> Option<MsgPacket> {
+        Some(forward_packet(packet.part(index)?, tk))
+    }
+
+    pub fn plt_proc_multi_packet(sess: &Edge, packet: &mut MultiMsgPacket) -> bool {
+        match db_ik::query_hk(sess) {
+            Some(hk) => {
+                packet.parts.iter_mut().for_each(|part| part.hk = hk);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn store_multi_tag(packet: &MultiMsgPacket) {
+        let tags: Vec<String> = packet.parts.iter().map(|part| encode(part.p_tag)).collect();
+        let _ = db_tag::add(&tags);
+    }
+
+    pub fn receive_multi_packet(packet: &MultiMsgPacket) -> bool {
+        !packet.parts.is_empty() && packet.parts.iter().all(receive_packet)
+    }
+
+    // a report names one part, so tracing follows that part's chain only
+    pub fn submit_part_report(packet: &MultiMsgPacket, index: usize, sess: &Edge) -> Option<(MsgReport, Edge)> {
+        let part = packet.part(index)?;
+        Some(submit_report(&part.tag_key, &part.payload, sess))
+    }
+
 }
 
 
+// Sealed sender: the sender's contribution to hk travels encrypted to the tracing authority,
+// so routine delivery never reveals the sender to the relaying server.
+pub mod sealed_sender {
+    use base64::encode;
+    use serde::{Serialize, Deserialize};
+    use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+    use crate::db::{db_tag, db_ik};
+    use crate::message::messaging::{Edge, MsgPacket, MsgReport, send_packet, Uid, UID_LEN};
+    use crate::tool::algos::{tk_gen, hk_gen, prf_gen, tag_proc, tag_exists};
+    use crate::tool::utils::{hash, encryption, try_decryption, encryption_bytes, try_decryption_bytes};
+
+    #[derive(Clone)]
+    pub struct TraceAuthority {
+        secret: StaticSecret,
+        pub public: PublicKey,
+    }
+
+    impl TraceAuthority {
+        pub fn new() -> Self {
+            let secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
+            let public = PublicKey::from(&secret);
+            TraceAuthority { secret, public }
+        }
+
+        // None if the box was not sealed to this authority under context
+        fn open_bound(&self, sealed: &SealedBox, context: &[u8]) -> Option<[u8; 32]> {
+            let shared = self.secret.diffie_hellman(&PublicKey::from(sealed.epk));
+            try_decryption(&box_key(shared.as_bytes(), context), &sealed.ct())
+        }
+
+        pub fn open_bytes(&self, sealed: &SealedBox) -> Option<[u8; 32]> {
+            self.open_bound(sealed, &[])
+        }
+
+        // returns (sid, hk) sealed in the envelope, which only opens with the prf of the packet it came with
+        pub fn open(&self, env: &SealedSender, prf: &[u8; 32]) -> Option<(Uid, [u8; 16])> {
+            let plaintext = self.open_bound(env, prf)?;
+            let sid = Uid::from_bytes(plaintext[..UID_LEN].try_into().unwrap());
+            Some((sid, plaintext[UID_LEN..UID_LEN + 16].try_into().unwrap()))
+        }
+
+        pub fn open_key(&self, sealed: &SealedBox) -> Option<[u8; 16]> {
+            Some(self.open_bytes(sealed)?[..16].try_into().unwrap())
+        }
+
+        pub fn open_data(&self, sealed: &SealedData) -> Option<Vec<u8>> {
+            let shared = self.secret.diffie_hellman(&PublicKey::from(sealed.epk));
+            try_decryption_bytes(&box_key(shared.as_bytes(), &[]), &sealed.ct)
+        }
+    }
+
+    impl Default for TraceAuthority {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    // 32 bytes encrypted to the tracing authority under a fresh key per box
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct SealedBox {
+        pub epk: [u8; 32],
+        pub ct_1: [u8; 32],
+        pub ct_2: [u8; 16],
+    }
+
+    pub type SealedSender = SealedBox;
+
+    // the box key also covers a context, so a box only opens for what it was sealed with
+    fn box_key(shared: &[u8; 32], context: &[u8]) -> [u8; 16] {
+        hash(&(encode(shared) + &encode(context)))
+    }
+
+    impl SealedBox {
+        fn seal_bound(plaintext: &[u8; 32], ta_pk: &PublicKey, context: &[u8]) -> Self {
+            let esk = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+            let epk = PublicKey::from(&esk);
+            let shared = esk.diffie_hellman(ta_pk);
+            let ct = encryption(&box_key(shared.as_bytes(), context), plaintext);
+            SealedBox { epk: epk.to_bytes(), ct_1: ct[..32].try_into().unwrap(), ct_2: ct[32..].try_into().unwrap() }
+        }
+
+        pub fn seal_bytes(plaintext: &[u8; 32], ta_pk: &PublicKey) -> Self {
+            Self::seal_bound(plaintext, ta_pk, &[])
+        }
+
+        // plaintext = sid || hk, bound to the prf of the packet
+        pub fn seal(sid: &Uid, hk: &[u8; 16], prf: &[u8; 32], ta_pk: &PublicKey) -> Self {
+            let mut plaintext: [u8; 32] = [0; 32];
+            plaintext[..UID_LEN].copy_from_slice(&sid.to_bytes());
+            plaintext[UID_LEN..UID_LEN + 16].copy_from_slice(hk);
+            Self::seal_bound(&plaintext, ta_pk, prf)
+        }
+
+        pub fn seal_key(key: &[u8; 16], ta_pk: &PublicKey) -> Self {
+            let mut plaintext: [u8; 32] = [0; 32];
+            plaintext[..16].copy_from_slice(key);
+            Self::seal_bytes(&plaintext, ta_pk)
+        }
+
+        // opens a key sealed to a moderator set with the dh output on epk, put together from their partials
+        pub(crate) fn open_key_shared(&self, shared: &[u8; 32]) -> Option<[u8; 16]> {
+            Some(try_decryption(&box_key(shared, &[]), &self.ct())?[..16].try_into().unwrap())
+        }
+
+        fn ct(&self) -> [u8; 48] {
+            let mut ct: [u8; 48] = [0; 48];
+            ct[..32].copy_from_slice(&self.ct_1);
+            ct[32..].copy_from_slice(&self.ct_2);
+            ct
+        }
+    }
+
+    // like SealedBox, for a plaintext of any length
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct SealedData {
+        pub epk: [u8; 32],
+        pub ct: Vec<u8>,
+    }
+
+    impl SealedData {
+        pub fn seal(plaintext: &[u8], ta_pk: &PublicKey) -> Self {
+            let esk = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+            let epk = PublicKey::from(&esk);
+            let shared = esk.diffie_hellman(ta_pk);
+            SealedData { epk: epk.to_bytes(), ct: encryption_bytes(&box_key(shared.as_bytes(), &[]), plaintext) }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct SealedPacket {
+        pub packet: MsgPacket,
+        pub sealed: SealedSender,
+    }
+
+    pub fn send_sealed_packet(message: &String, prev_key: &[u8; 16], tk: &[u8; 16], sid: &Uid, ta_pk: &PublicKey) -> SealedPacket {
+        let packet = send_packet(message, prev_key, tk);
+        let sealed = SealedSender::seal(sid, &hk_gen(tk), &packet.prf, ta_pk);
+        SealedPacket { packet, sealed }
+    }
+
+    // The relaying server stores the tag once the tracing authority has opened the envelope
+    // for this packet and checked the tag against the registered sender and hk in it.
+    // That the hk is the one of the sender's session is checked on a report, by ta_verify_report.
+    pub fn plt_proc_sealed_packet(ta: &TraceAuthority, packet: &SealedPacket) -> bool {
+        let valid = match ta.open(&packet.sealed, &packet.packet.prf) {
+            Some((sid, hk)) => (packet.packet.p_tag == tag_proc(&packet.packet.prf, &hk)) && db_ik::exists(&[sid])[0],
+            None => false,
+        };
+        if valid {
+            let _ = db_tag::add(&vec![encode(packet.packet.p_tag)]);
+        }
+        valid
+    }
+
+    // the receiver forwards the envelope it got with the message, which it cannot open itself
+    pub fn submit_sealed_report(tag_key: &[u8; 16], message: &str, packet: &SealedPacket) -> (MsgReport, SealedSender) {
+        (MsgReport { key: *tag_key, payload: message.to_string() }, packet.sealed.clone())
+    }
+
+    // the authority opens the envelope and checks it against the session's hk and db_tag
+    pub fn ta_verify_report(ta: &TraceAuthority, rid: &Uid, report: &MsgReport, sealed: &SealedSender) -> Option<Uid> {
+        let prf = prf_gen(&report.key, &report.payload);
+        let (sid, hk) = ta.open(sealed, &prf)?;
+        let sess_hk = db_ik::query_hk(&Edge::new(&sid, rid))?;
+        (hk == sess_hk && db_tag::exists(&encode(tag_proc(&prf, &hk)))).then_some(sid)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate base64;
@@ -156,6 +605,7 @@ mod tests {
     use test::Bencher;
     use crate::db::{db_tag, db_nbr, db_ik};
     use crate::message::messaging::*;
+    use crate::message::sealed_sender::*;
     use crate::tool::algos::*;
     use aes_gcm::aead::{Aead, AeadCore, OsRng};
     use double_ratchet_2::ratchet::Ratchet;
@@ -170,6 +620,111 @@ mod tests {
         assert!(receive_packet(&packet));
     }
 
+    #[test]
+    fn snd_rcv_multi_msg() {
+        let tk = rand::random::<[u8; 16]>();
+        let parts = vec![
+            (encode(rand::random::<[u8; 16]>()), [0; 16]),
+            (encode(rand::random::<[u8; 16]>()), rand::random::<[u8; 16]>()),
+        ];
+        let packet = send_multi_packet(&parts, &tk);
+        assert!(receive_multi_packet(&packet));
+        assert_ne!(packet.part(0).unwrap().tag_key, packet.part(1).unwrap().tag_key);
+        assert!(packet.part(2).is_none());
+
+        let fwd_tk = rand::random::<[u8; 16]>();
+        assert!(forward_part(&packet, 2, &fwd_tk).is_none());
+        assert!(submit_part_report(&packet, 2, &Edge::new(&Uid(1), &Uid(2))).is_none());
+        let fwd_packet = forward_part(&packet, 1, &fwd_tk).unwrap();
+        assert!(receive_packet(&fwd_packet));
+        assert_eq!(prev_key(&fwd_packet.tag_key, &fwd_tk), packet.part(1).unwrap().tag_key);
+    }
+
+    #[test]
+    fn seal_open_sender() {
+        let ta = TraceAuthority::new();
+        let sid: Uid = rand::random::<Uid>();
+        let tk = rand::random::<[u8; 16]>();
+        let message = encode(rand::random::<[u8; 16]>());
+
+        let packet = send_sealed_packet(&message, &[0; 16], &tk, &sid, &ta.public);
+        assert!(receive_packet(&packet.packet));
+        assert_eq!(ta.open(&packet.sealed, &packet.packet.prf), Some((sid, hk_gen(&tk))));
+        let data = SealedData::seal(message.as_bytes(), &ta.public);
+        assert_eq!(ta.open_data(&data), Some(message.as_bytes().to_vec()));
+
+        // the envelope does not open for another packet, nor for another authority
+        let other = send_sealed_packet(&message, &[0; 16], &tk, &sid, &ta.public);
+        assert_eq!(ta.open(&packet.sealed, &other.packet.prf), None);
+        assert_eq!(TraceAuthority::new().open(&packet.sealed, &packet.packet.prf), None);
+        let swapped = SealedPacket { packet: other.packet, sealed: packet.sealed.clone() };
+        assert!(!plt_proc_sealed_packet(&ta, &swapped));
+        let mut forged = send_sealed_packet(&message, &[0; 16], &tk, &sid, &ta.public);
+        forged.packet.p_tag = rand::random::<[u8; 32]>();
+        assert!(!plt_proc_sealed_packet(&ta, &forged));
+    }
+
+    #[test]
+    fn uid_encoding() {
+        // wider than u64, as a UUID would be
+        let uid = Uid(u128::MAX - 7);
+        assert_eq!(uid.to_string().parse::<Uid>(), Ok(uid));
+        assert_eq!(Uid::from_bytes(uid.to_bytes()), uid);
+        assert_eq!(serde_json::from_str::<Uid>(&serde_json::to_string(&uid).unwrap()).unwrap(), uid);
+        let value = redis::Value::BulkString(redis::ToRedisArgs::to_redis_args(&uid).concat());
+        assert_eq!(redis::from_redis_value::<Uid>(value).unwrap(), uid);
+        assert!(redis::from_redis_value::<Uid>(redis::Value::BulkString(b"user".to_vec())).is_err());
+    }
+
+    #[test]
+    fn fwd_hop_counter() {
+        let message = encode(rand::random::<[u8; 16]>());
+        let mut packet = send_packet(&message, &[0; 16], &rand::random::<[u8; 16]>());
+        for _ in 0..3 {
+            packet = forward_packet(&packet, &rand::random::<[u8; 16]>());
+            assert!(receive_packet(&packet));
+        }
+        assert_eq!(packet.hop, 3);
+
+        // resetting the counter without the seed is rejected
+        let mut reset_packet = forward_packet(&packet, &rand::random::<[u8; 16]>());
+        reset_packet.hop = 0;
+        assert!(!receive_packet(&reset_packet));
+
+        let policy = HopPolicy::new(2, 3);
+        assert_eq!(plt_check_hop(&policy, &reset_packet), HopAction::Block);
+        assert!(client_forward(&policy, &packet, &rand::random::<[u8; 16]>()).is_none());
+
+        // a forward without the previous packet carries no counter, which is accepted but labelled
+        let bare_packet = send_packet(&message, &packet.tag_key, &rand::random::<[u8; 16]>());
+        assert!(!bare_packet.has_hop_counter() & receive_packet(&bare_packet));
+        assert!(!forward_packet(&bare_packet, &rand::random::<[u8; 16]>()).has_hop_counter());
+        assert_eq!(plt_check_hop(&policy, &bare_packet), HopAction::Label);
+        let mut json: serde_json::Value = serde_json::to_value(&bare_packet).unwrap();
+        ["hop", "hop_proof", "hop_anchor"].iter().for_each(|field| { json.as_object_mut().unwrap().remove(*field); });
+        assert!(receive_packet(&serde_json::from_value::<MsgPacket>(json).unwrap()));
+    }
+
+    #[test]
+    fn plt_hop_anchor() {
+        let message = encode(rand::random::<[u8; 16]>());
+        let policy = HopPolicy::new(2, 3);
+        let mut packet = send_packet(&message, &[0; 16], &rand::random::<[u8; 16]>());
+        assert_eq!(plt_check_hop(&policy, &packet), HopAction::Deliver);
+        for _ in 0..3 {
+            packet = forward_packet(&packet, &rand::random::<[u8; 16]>());
+        }
+        assert_eq!(plt_check_hop(&policy, &packet), HopAction::Label);
+
+        // a valid counter the forwarder started itself does not pass as hop 1
+        let mut fresh_packet = forward_packet(&packet, &rand::random::<[u8; 16]>());
+        let (seed, anchor) = hop_counter_gen();
+        (fresh_packet.hop, fresh_packet.hop_proof, fresh_packet.hop_anchor) = (1, hop_next(&seed), anchor);
+        assert!(receive_packet(&fresh_packet));
+        assert_eq!(plt_check_hop(&policy, &fresh_packet), HopAction::Block);
+        db_tag::clear();
+    }
+
     #[test]
     fn test_send_packet() {
         let tk = rand::random::<[u8; 16]>();
@@ -180,8 +735,8 @@ mod tests {
 
     #[test]
     fn report_msg() {
-        let sid: u32 = rand::random::<u32>();
-        let rid: u32 = rand::random::<u32>();
+        let sid: Uid = rand::random::<Uid>();
+        let rid: Uid = rand::random::<Uid>();
         let message = rand::random::<[u8; 16]>();
 
         let ik: [u8; 16] = rand::random::<[u8; 16]>();
@@ -190,7 +745,7 @@ mod tests {
         let sess = Edge::new( &sid, &rid);
         let tag = proc_tag_gen(&tag_key, &tk, &encode(message));
 
-        let _ = db_ik::add(&vec![IdKey {id: sess.sid, key: ik}]);
+        let _ = open_session(&sess, &ik);
         let _ = db_nbr::add(&vec![sess.clone()]);
         let _ = db_tag::add(&vec![encode(tag)]);
 
@@ -206,14 +761,12 @@ mod tests {
         let mut count: Duration = Default::default();
         let loop_count = 1000;
         for _ in 0..loop_count {
-            let tk = rand::random::<[u8; 16]>();
-            let uid = rand::random::<u32>();
-            let id_key = IdKey::rand_key_gen(uid);
-            db_ik::add(&vec![id_key]).ok();
+            let sess = Edge::new(&rand::random::<Uid>(), &rand::random::<Uid>());
+            let mut packet = send_packet(&"message".to_string(), &[0; 16], &rand::random::<[u8; 16]>());
+            open_session(&sess, &rand::random::<[u8; 16]>()).ok();
     
             let st = Instant::now();
-            let _ = hk_gen(&tk);
-            db_ik::query(&vec![uid]);
+            plt_proc_packet(&sess, &mut packet);
             let et = st.elapsed();
             count += et;
         }
@@ -1,39 +1,95 @@
 #![allow(dead_code, unused_imports)]
 
 pub mod rwc_eval {
-    use std::{collections::HashMap, time::{SystemTime, UNIX_EPOCH}, fs::File, io::Write, vec};
+    use std::{collections::{HashMap, HashSet}, time::{SystemTime, UNIX_EPOCH, Instant}, fs::File, io::{self, Write}, vec};
 
     use base64::encode;
     use petgraph::{prelude::UnGraph, visit::EdgeRef};
+    use serde::{Deserialize, Serialize};
 
-    use crate::{simulation::{sir, utils::{vec_to_graph, dedup_vec_edges}, fuzzy_traceback::{fuzzy_trace_ours, any_leaf, self, degree_analysis}}, message::messaging::{MsgReport, MsgPacket, IdKey, send_packet, Edge}, db::{db_tag, db_ik, db_nbr}, tool::{algos::tk_gen, utils::hash}};
-    use crate::trace::traceback;
+    use crate::{simulation::{sir, spread::{SpreadModel, SeedSelection, Model, Sir, origin_of}, temporal::TemporalRate, adversary::{self, Adversaries}, utils::{import_graph, vec_to_graph, dedup_vec_edges, gen_seed_data_file, SimRng, SimSeed}, fuzzy_traceback::{fuzzy_trace_ours, calc_fuz_val, any_leaf, self, degree_analysis}}, message::messaging::{MsgReport, MsgPacket, IdKey, send_packet, Edge, Uid}, db::{db_tag, db_ik, db_nbr}, tool::{algos::tk_gen, utils::hash}};
+    use crate::trace::{traceback, trace_scope::{TraceScope, ScopePolicy}};
+    use crate::warrant::trace_warrant::{ModeratorSet, ModeratorShare, WarrantRequest, TraceWarrant};
+    use crate::experiment::{runner::MAX_DRAWS, spec::{Spec, Study}};
 
-    #[derive(Debug,PartialEq)]
+    #[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+    #[serde(rename_all = "snake_case")]
     pub(crate) enum Dataset {
+        #[serde(rename = "college_im")]
         CollegeIM,
         EuEmail,
+        // the raw contacts behind EuEmail, not bundled, see datasets/README.md
+        EuEmailTemporal,
     }
 
+    impl Dataset {
+        pub fn is_temporal(&self) -> bool {
+            matches!(self, Dataset::EuEmailTemporal)
+        }
+    }
+
+    // a user of a temporal dataset forwards for a day after receiving the message
+    pub const TEMPORAL_PERIOD: u64 = 86400;
+
     // Here, we set the starting node manually to ensure consistency in repeated experiments. However, our implementation also holds for random start node.
     pub fn select_dataset(data: &Dataset) -> (String, usize, usize, f32, f32) {
         match data {
             Dataset::CollegeIM => ("./datasets/message.txt".to_string(), 719, 20, 0.05, 0.6),
             Dataset::EuEmail => ("./datasets/email.txt".to_string(), 1, 20, 0.03, 0.6),
+            Dataset::EuEmailTemporal => ("./datasets/email-Eu-core-temporal.txt".to_string(), 1, 20, 0.03, 0.6),
+        }
+    }
+
+    // the SIR model of a dataset, or its time-respecting one when the dataset is temporal
+    pub fn dataset_model(data: &Dataset) -> Model {
+        let (_, _, _, s2i, i2r) = select_dataset(data);
+        match data.is_temporal() {
+            true => Model::Temporal(TemporalRate { s2i, period: TEMPORAL_PERIOD }),
+            false => Model::Sir(Sir { s2i, i2r }),
         }
     }
 
-    pub fn eval_fuzz_trace_runtime(trace_fpr: &f32, st_node: &usize, s2i: &f32, i2r: &f32, loop_index: &usize, sys_graph: &UnGraph<usize,()>,  fwd_out_dir: &String) -> Vec<f64> {
+    // one moderator approving a full spread trace of every evaluated report, the identity keys are sealed to it
+    struct Moderator {
+        set: ModeratorSet,
+        share: ModeratorShare,
+    }
+
+    impl Moderator {
+        fn new() -> Self {
+            let (set, mut shares) = ModeratorSet::setup(1, &[Uid(0)], ScopePolicy::all());
+            Moderator { set, share: shares.remove(0) }
+        }
+
+        fn warrant(&self, report: &MsgReport) -> TraceWarrant<'_> {
+            let mut request = WarrantRequest::new(&self.set, report, TraceScope::FullSpread, "evaluation");
+            request.approve(&self.share).unwrap();
+            request.issue().unwrap()
+        }
+    }
+
+    // how an evaluation draws its forward graphs: `round` rounds of the model, drawn again while under `min_edges` edges
+    #[derive(Clone, Copy)]
+    pub struct Forwarding<'a> {
+        pub model: &'a dyn SpreadModel,
+        pub round: usize,
+        pub min_edges: usize,
+    }
+
+    pub fn eval_fuzz_trace_runtime(trace_fpr: &f32, st_node: &usize, forwarding: &Forwarding, seed: &SimSeed, loop_index: &usize, sys_graph: &UnGraph<usize,()>,  fwd_out_dir: &String) -> io::Result<Vec<f64>> {
         let mut record: Vec<Vec<f64>> = Vec::new();
         // 1. init tracing keys
-        let map_id_ik = sys_ik_init(&sys_graph);
+        let moderator = Moderator::new();
+        let map_id_ik = sys_ik_init(&sys_graph, &moderator);
         for i in 0..*loop_index{
+            let trial = seed.trial(i as u64);
+            let mut rng = trial.rng();
             // 2. generate fwd and fuzz graph
-            let (_, fwd_edges, fuzz_edges_hmap) = gen_fwd_fuzz_edges(&sys_graph, st_node, trace_fpr, s2i, i2r);
+            let (_, fwd_edges, fuzz_edges_hmap) = gen_fwd_fuzz_edges(&sys_graph, st_node, trace_fpr, forwarding, i, &mut rng)?;
             let fuzz_edges: Vec<(usize,usize)> = fuzz_edges_hmap.into_iter().map(|(k,_)| k).collect();
 
             // 2-1. graph analysis
-            write_vec_edges_to_file(&fwd_edges, &format!("{}-{}.txt", fwd_out_dir, i));
+            write_vec_edges_to_file(&fwd_edges, &format!("{}-{}.txt", fwd_out_dir, i), &trial);
             let (fwd_graph, _) = vec_to_graph(&fwd_edges);
             let (fuzz_graph, _) = vec_to_graph(&fuzz_edges);
             let (fwd_degree, fuzz_degree) =  (degree_analysis(&fwd_graph, &sys_graph), degree_analysis(&fuzz_graph, &sys_graph));
@@ -41,17 +97,20 @@ pub mod rwc_eval {
             // 3. mock sends for fuzz_edges
             let message = "message".to_string() + &i.to_string();
 
-            let (_, first_packet) = frist_pkg(&message, &(*st_node as u32));
-            let mut rcv_keys: HashMap<u32,[u8;16]> = HashMap::new();
-            let mut expl_user: Vec<u32> = Vec::new();
-            recursive_mock_send(&(*st_node as u32), &first_packet.tag_key, &message, &mut expl_user, &fuzz_edges, &map_id_ik, &mut rcv_keys);
+            // the injecting sender sits outside the system graph, whose ids are 0..node_count
+            let (_, first_packet) = frist_pkg(&message, &Uid::from(sys_graph.node_count()), &Uid::from(*st_node), &moderator);
+            let mut rcv_keys: HashMap<Uid,[u8;16]> = HashMap::new();
+            let mut expl_user: Vec<Uid> = Vec::new();
+            recursive_mock_send(&Uid::from(*st_node), &first_packet.tag_key, &message, &mut expl_user, &fuzz_edges, &map_id_ik, &mut rcv_keys);
 
             // 4. traceback
-            let trace_st_node: u32 = fuzzy_traceback::any_leaf(&fwd_graph) as u32;
+            let Some(trace_st_node) = fuzzy_traceback::any_leaf(&fwd_graph).map(Uid::from) else { continue };
             let trace_st_key = rcv_keys.get(&trace_st_node).unwrap();
+            let report = MsgReport {key: *trace_st_key, payload: message};
+            let warrant = moderator.warrant(&report);
             
             let t_start = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-            let _ = traceback::tracing(&MsgReport {key: *trace_st_key, payload: message}, &trace_st_node);
+            let _ = traceback::tracing(&report, &trace_st_node, &warrant);
             // convert edges to Vec<(usize,usize)>
             let t_end = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
             // assert_eq!(trace_edges.len()-1, fuzz_edges.len());
@@ -60,13 +119,194 @@ pub mod rwc_eval {
             // 5. record as fwd_nodes, fwd_edges, fuzz_nodes, fuzz_edges, runtime
             record.push(vec![fwd_graph.node_count() as f64, fwd_edges.len() as f64, fwd_degree as f64,  fuzz_graph.node_count() as f64, fuzz_edges.len() as f64, fuzz_degree as f64, (t_end.as_millis() - t_start.as_millis()) as f64]);
         }
-        matrix_aver(&record)
+        Ok(matrix_aver(&record))
+    }
+
+    // time the per-round nbr and identity key lookups over the frontiers of a fuzz graph, split vs fused
+    pub fn eval_lookup_latency(trace_fpr: &f32, st_node: &usize, forwarding: &Forwarding, seed: &SimSeed, loop_index: &usize, sys_graph: &UnGraph<usize,()>) -> io::Result<Vec<f64>> {
+        let mut record: Vec<Vec<f64>> = Vec::new();
+        let moderator = Moderator::new();
+        let _ = sys_ik_init(sys_graph, &moderator);
+        for i in 0..*loop_index {
+            let mut rng = seed.trial(i as u64).rng();
+            let report = MsgReport {key: [0; 16], payload: "lookup".to_string() + &i.to_string()};
+            let warrant = moderator.warrant(&report);
+            let (start_node, _, fuzz_edges_hmap) = gen_fwd_fuzz_edges(sys_graph, st_node, trace_fpr, forwarding, i, &mut rng)?;
+            let fuzz_edges: Vec<(usize,usize)> = fuzz_edges_hmap.into_keys().collect();
+            let frontiers = bfs_frontiers(&Uid::from(start_node), &fuzz_edges);
+
+            let (mut split_ms, mut fused_ms) = (0.0, 0.0);
+            for frontier in &frontiers {
+                let t_start = Instant::now();
+                let _ = traceback::db_query_nbrs_split(&report, frontier, &warrant);
+                split_ms += t_start.elapsed().as_secs_f64() * 1000.0;
+                let t_start = Instant::now();
+                let _ = traceback::db_query_nbrs_fused(&report, frontier, &warrant);
+                fused_ms += t_start.elapsed().as_secs_f64() * 1000.0;
+            }
+            let nodes: usize = frontiers.iter().map(|f| f.len()).sum();
+            record.push(vec![frontiers.len() as f64, nodes as f64, split_ms, fused_ms]);
+        }
+        Ok(matrix_aver(&record))
+    }
+
+    // how a campaign picks its seed accounts, and whether one sender outside the system graph pushes the message to all of them
+    #[derive(Clone, Debug)]
+    pub struct Campaign {
+        pub selection: SeedSelection,
+        pub shared_sender: bool,
+    }
+
+    // spread from several seeds and trace from one reached user, with the fuzzy traceback and with the real tracing,
+    // writing per reached user the seed it got the message from and whether each recovered it, see `analysis`
+    pub fn eval_seed_recall(trace_fpr: &f32, campaign: &Campaign, forwarding: &Forwarding, seed: &SimSeed, loop_index: &usize, sys_graph: &UnGraph<usize,()>, out_dir: &String) -> io::Result<Vec<f64>> {
+        let mut record: Vec<Vec<f64>> = Vec::new();
+        let moderator = Moderator::new();
+        let map_id_ik = sys_ik_init(sys_graph, &moderator);
+        for i in 0..*loop_index {
+            let trial = seed.trial(i as u64);
+            let mut rng = trial.rng();
+            // 1. spread from the selected seeds
+            let (st_nodes, infected_edges, node_src) = (0..MAX_DRAWS)
+                .map(|_| {
+                    let st_nodes = campaign.selection.select(sys_graph, &mut rng);
+                    let (infected_edges, node_src) = forwarding.model.spread_seeds(&forwarding.round, &st_nodes, sys_graph, &mut rng);
+                    (st_nodes, infected_edges, node_src)
+                })
+                .find(|(_, infected_edges, _)| infected_edges.len() >= forwarding.min_edges)
+                .ok_or_else(|| no_spread(i, forwarding))?;
+            let origin = origin_of(&node_src);
+            let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&infected_edges);
+            let Some(trace_st_node) = any_leaf(&fwd_graph) else { continue };
+
+            // 2. fuzzy traceback
+            let (bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &trace_st_node, trace_fpr, &mut rng);
+            let (memb_val, _) = calc_fuz_val(&(*trace_fpr as f64), &max_depth, &trace_st_node, &bwd_traced_edges, &mut fwd_traced_edges, sys_graph);
+            gen_seed_data_file(&origin, &memb_val, format!("{}-{}-fuzzy.txt", out_dir, i), &trial);
+
+            // 3. mock sends for the forwarded edges, every seed gets the message from a sender outside the system graph
+            let message = "message".to_string() + &i.to_string();
+            let mut rcv_keys: HashMap<Uid,[u8;16]> = HashMap::new();
+            let mut expl_user: Vec<Uid> = Vec::new();
+            for (k, st_node) in st_nodes.iter().enumerate() {
+                let snd = Uid::from(sys_graph.node_count() + if campaign.shared_sender { 0 } else { k });
+                let (_, first_packet) = frist_pkg(&message, &snd, &Uid::from(*st_node), &moderator);
+                recursive_mock_send(&Uid::from(*st_node), &first_packet.tag_key, &message, &mut expl_user, &infected_edges, &map_id_ik, &mut rcv_keys);
+            }
+
+            // 4. traceback
+            let trace_st_key = rcv_keys.get(&Uid::from(trace_st_node)).unwrap();
+            let report = MsgReport {key: *trace_st_key, payload: message};
+            let trace_edges = traceback::tracing(&report, &Uid::from(trace_st_node), &moderator.warrant(&report)).unwrap();
+            let traced: HashMap<usize,f64> = trace_edges.iter().flat_map(|e| [e.sid, e.rid])
+                .filter(|uid| uid.index() < sys_graph.node_count())
+                .map(|uid| (uid.index(), 100.0))
+                .collect();
+            gen_seed_data_file(&origin, &traced, format!("{}-{}-trace.txt", out_dir, i), &trial);
+
+            // 5. record as seeds, reached users, fuzzy traced users, traced users
+            record.push(vec![st_nodes.len() as f64, origin.len() as f64, memb_val.len() as f64, traced.len() as f64]);
+        }
+        Ok(matrix_aver(&record))
     }
 
+    // spread, let the adversaries act on it and run the fuzzy traceback from a leaf, or from a false reporter if there are any
+    pub fn eval_adversary(trace_fpr: &f32, adversaries: &Adversaries, st_node: &usize, forwarding: &Forwarding, seed: &SimSeed, loop_index: &usize, sys_graph: &UnGraph<usize,()>) -> io::Result<Vec<f64>> {
+        let mut record: Vec<Vec<f64>> = Vec::new();
+        for i in 0..*loop_index {
+            let mut rng = seed.trial(i as u64).rng();
+            let (infected_edges, node_src) = (0..MAX_DRAWS)
+                .map(|_| forwarding.model.spread(&forwarding.round, st_node, sys_graph, &mut rng))
+                .find(|(infected_edges, _)| infected_edges.len() >= forwarding.min_edges)
+                .ok_or_else(|| no_spread(i, forwarding))?;
+            let behaviours = adversaries.assign(sys_graph, &mut rng);
+            let spread = adversary::apply(sys_graph, &infected_edges, &node_src, &behaviours);
+            let (fwd_graph, _) = vec_to_graph(&spread.edges);
+            // a false reporter that got the message first
+            let reporter = match spread.forged.iter().min_by_key(|u| (!spread.node_src.contains_key(u), **u)) {
+                Some(reporter) => *reporter,
+                None => {
+                    let Some(leaf) = any_leaf(&fwd_graph) else { continue };
+                    leaf
+                },
+            };
+
+            // fuzzy traceback over the chain the report links to
+            let (trace_edges, trace_src) = spread.traceable(&reporter);
+            let (trace_graph, trace_id_map) = vec_to_graph(&trace_edges);
+            let (bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(&spread.sys_graph, &trace_graph, &trace_id_map, &trace_src, &reporter, trace_fpr, &mut rng);
+            let memb_val = match bwd_traced_edges.is_empty() {
+                // nothing links back from the reporter
+                true => HashMap::from([(reporter, 100.0)]),
+                false => calc_fuz_val(&(*trace_fpr as f64), &max_depth, &reporter, &bwd_traced_edges, &mut fwd_traced_edges, &spread.sys_graph).0,
+            };
+
+            let (reached_val, unreached_val): (Vec<_>, Vec<_>) = memb_val.iter().partition(|(node, _)| spread.node_src.contains_key(node));
+            let mean = |vals: &[(&usize, &f64)]| match vals.is_empty() {
+                true => 0.0,
+                false => vals.iter().map(|(_, val)| **val).sum::<f64>() / vals.len() as f64,
+            };
+            // record as forwards, traceable forwards, traced users, recall of reached users, seed found,
+            // mean fuzzy value of traced users that were reached and that were not
+            record.push(vec![spread.edges.len() as f64, trace_edges.len() as f64, memb_val.len() as f64, reached_val.len() as f64 / spread.node_src.len() as f64,
+                (memb_val.contains_key(st_node) as usize) as f64, mean(&reached_val), mean(&unreached_val)]);
+        }
+        Ok(matrix_aver(&record))
+    }
+
+    // the trace time or lookup latency record of every sweep point of a spec, with trials as loop_index,
+    // and the forward graphs of trace time written to out_dir/<point>
+    pub fn eval_spec(spec: &Spec, seed: &SimSeed) -> io::Result<Vec<(Model, Vec<f64>)>> {
+        let (_, st_node, round) = spec.graph()?;
+        let trials = spec.trials()?;
+        let (sys_graph, contacts) = spec.load_graph()?;
+        let mut records = Vec::new();
+        for (count, model) in spec.models()?.into_iter().enumerate() {
+            db_nbr::clear();
+            db_ik::clear();
+            db_tag::clear();
+            let spread = model.over(contacts.as_deref()).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "a temporal model needs a temporal graph"))?;
+            let forwarding = Forwarding { model: spread.as_ref(), round, min_edges: spec.min_edges };
+            let record = match spec.study {
+                Study::TraceTime => eval_fuzz_trace_runtime(&spec.trace_fpr, &st_node, &forwarding, seed, &trials, &sys_graph, &format!("{}/{}", spec.out_dir, count))?,
+                Study::LookupLatency => eval_lookup_latency(&spec.trace_fpr, &st_node, &forwarding, seed, &trials, &sys_graph)?,
+                Study::Utility => return Err(io::Error::new(io::ErrorKind::InvalidInput, "utility specs are run by experiment::runner::run_spec")),
+            };
+            records.push((model, record));
+        }
+        db_nbr::clear();
+        db_ik::clear();
+        db_tag::clear();
+        Ok(records)
+    }
+
+    // users grouped by their hop distance from root, i.e. the frontier of each search round
+    fn bfs_frontiers(root: &Uid, edge_list: &Vec<(usize,usize)>) -> Vec<Vec<Uid>> {
+        let mut adjacency: HashMap<Uid, Vec<Uid>> = HashMap::new();
+        for (a, b) in edge_list {
+            adjacency.entry(Uid::from(*a)).or_default().push(Uid::from(*b));
+            adjacency.entry(Uid::from(*b)).or_default().push(Uid::from(*a));
+        }
+        let mut visited: HashSet<Uid> = HashSet::from([*root]);
+        let mut frontiers: Vec<Vec<Uid>> = Vec::new();
+        let mut frontier: Vec<Uid> = vec![*root];
+        while !frontier.is_empty() {
+            let next: Vec<Uid> = frontier.iter()
+                .flat_map(|from| adjacency.get(from).into_iter().flatten())
+                .filter(|to| visited.insert(**to))
+                .copied()
+                .collect();
+            frontiers.push(std::mem::replace(&mut frontier, next));
+        }
+        frontiers
+    }
+
+    // empty when no trial got a record
     fn matrix_aver(record: &Vec<Vec<f64>>) -> Vec<f64> {
         // compute the average of each column
         let mut avg: Vec<f64> = Vec::new();
-        for i in 0..record[0].len() {
+        let Some(first) = record.first() else { return avg };
+        for i in 0..first.len() {
             let mut sum: f64 = 0.0;
             for j in 0..record.len() {
                 sum += record[j][i];
@@ -76,9 +316,9 @@ pub mod rwc_eval {
         avg
     }
 
-    pub fn new_edge_gen(message: &String, sid: &u32, rid: &u32) -> MsgPacket {
-        let map_id_ik = db_ik::query(&vec![*sid]);
-        let tk = tk_gen(map_id_ik.get(sid).unwrap(), rid);
+    // the evaluated users' identity keys are derived from their ids
+    pub fn new_edge_gen(message: &String, sid: &Uid, rid: &Uid) -> MsgPacket {
+        let tk = tk_gen(&IdKey::id_as_key_gen(*sid).key, rid);
         let packet = send_packet(message, &[1;16], &tk);
         let _ = db_tag::add(&vec![encode(packet.p_tag)]);
         packet
@@ -94,26 +334,31 @@ pub mod rwc_eval {
         diff
     }
 
-    fn frist_pkg(message: &String, root: &u32) -> (u32, MsgPacket) {
-        let snd: u32 = 30000;
-        let sik = hash(&snd.to_string());
-        let _ = db_ik::add(&vec![IdKey{id: snd, key: sik}]);
+    fn frist_pkg(message: &String, snd: &Uid, root: &Uid, moderator: &Moderator) -> (Uid, MsgPacket) {
+        let snd = *snd;
+        let _ = db_ik::add(&[IdKey::id_as_key_gen(snd)], &moderator.set.public_key());
         let _ = db_nbr::add(&mut vec![Edge::new(&snd, &root)]);
         let pkg = new_edge_gen(&message, &snd, &root);
         (snd, pkg)
     }
 
-    fn gen_fwd_fuzz_edges(sys_graph: &UnGraph<usize,()>, st_node: &usize, trace_fpr: &f32, s2i: &f32, i2r: &f32) -> (usize, Vec<(usize,usize)>, HashMap<(usize,usize),(usize,usize)>) {
-        loop {
-            // Generate a forward graph （default is 20 rounds)
-            let (infected_edges, node_src) = sir::sir_spread(&20, st_node, s2i, i2r, &sys_graph.clone());
-            if infected_edges.len() < 50 {
+    // a trial fails as experiment::runner::Experiment::trial does, once MAX_DRAWS spreads were drawn
+    fn no_spread(trial: usize, forwarding: &Forwarding) -> io::Error {
+        let error = format!("trial {}: no spread of {} edges to report from in {} draws", trial, forwarding.min_edges, MAX_DRAWS);
+        io::Error::new(io::ErrorKind::InvalidInput, error)
+    }
+
+    fn gen_fwd_fuzz_edges(sys_graph: &UnGraph<usize,()>, st_node: &usize, trace_fpr: &f32, forwarding: &Forwarding, trial: usize, rng: &mut SimRng) -> io::Result<(usize, Vec<(usize,usize)>, HashMap<(usize,usize),(usize,usize)>)> {
+        for _draw in 0..MAX_DRAWS {
+            // Generate a forward graph
+            let (infected_edges, node_src) = forwarding.model.spread(&forwarding.round, st_node, sys_graph, rng);
+            if infected_edges.len() < forwarding.min_edges {
                 continue;
             }
             let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&infected_edges);
             // 2. Run fuzzy traceback to generate the fuzzy forward graph
-            let start_node = any_leaf(&fwd_graph);
-            let (bwd_traced_edges, mut fwd_traced_edges, _) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &trace_fpr);
+            let Some(start_node) = any_leaf(&fwd_graph) else { continue };
+            let (bwd_traced_edges, mut fwd_traced_edges, _) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &trace_fpr, rng);
             bwd_traced_edges.into_iter()
                 .filter(|((snd, rcv), _)| {
                     !fwd_traced_edges.contains_key(&(*rcv, *snd))
@@ -122,51 +367,53 @@ pub mod rwc_eval {
                 .into_iter().for_each(|(k,v)| {
                     fwd_traced_edges.insert((k.1, k.0), v);
             });
-            return (start_node, infected_edges, fwd_traced_edges);
+            return Ok((start_node, infected_edges, fwd_traced_edges));
         }
+        Err(no_spread(trial, forwarding))
     }
 
-    fn sys_ik_init(sys_graph: &UnGraph<usize, ()>) -> HashMap<u32, [u8;16]> {
+    fn sys_ik_init(sys_graph: &UnGraph<usize, ()>, moderator: &Moderator) -> HashMap<Uid, [u8;16]> {
         let raw_sys_edges: Vec<(usize,usize)> = sys_graph.edge_references().map(|e| (e.source().index(), e.target().index())).collect();
         let sys_edges = dedup_vec_edges(&raw_sys_edges);
         let mut sys_sess: Vec<Edge> = Vec::new();
-        let mut map_id_ik: HashMap<u32, [u8;16]> = HashMap::new();
+        let mut map_id_ik: HashMap<Uid, [u8;16]> = HashMap::new();
         for e in sys_edges {
-            map_id_ik.insert(e.0 as u32, hash(&e.0.to_string()));
-            map_id_ik.insert(e.1 as u32, hash(&e.1.to_string()));
-            sys_sess.push(Edge {sid: e.0 as u32, rid: e.1 as u32 })
+            map_id_ik.insert(Uid::from(e.0), hash(&e.0.to_string()));
+            map_id_ik.insert(Uid::from(e.1), hash(&e.1.to_string()));
+            sys_sess.push(Edge::new(&Uid::from(e.0), &Uid::from(e.1)))
         }
 
         // convert map_id_ik to Vec<IdKey>
         let mut id_ik: Vec<IdKey> = Vec::new();
         for (id, ik) in map_id_ik.clone() {
-            id_ik.push(IdKey {id: id as u32, key: ik});
+            id_ik.push(IdKey {id, key: ik});
         }
-        let _= db_ik::add(&id_ik);
+        let _= db_ik::add(&id_ik, &moderator.set.public_key());
         let _= db_nbr::add(&sys_sess);
         map_id_ik
     }
 
-    fn recursive_mock_send(root: &u32, key: &[u8; 16], message: &String, expl_user: &mut Vec<u32>, edge_list: &Vec<(usize,usize)>, map_id_ik: &HashMap<u32,[u8;16]>, keys: &mut HashMap<u32,[u8;16]>) {
+    fn recursive_mock_send(root: &Uid, key: &[u8; 16], message: &String, expl_user: &mut Vec<Uid>, edge_list: &Vec<(usize,usize)>, map_id_ik: &HashMap<Uid,[u8;16]>, keys: &mut HashMap<Uid,[u8;16]>) {
         match expl_user.contains(root) {
             false => {
                 expl_user.push(*root);
                 edge_list.into_iter()
-                .filter(|(sid, _)| (*sid as u32) == *root)
+                .filter(|(sid, _)| Uid::from(*sid) == *root)
                 .for_each(|(sid,rid)| {
-                    let tk = tk_gen(map_id_ik.get(&(*sid as u32)).unwrap(), &(*rid as u32));
+                    let tk = tk_gen(map_id_ik.get(&Uid::from(*sid)).unwrap(), &Uid::from(*rid));
                     let packet = send_packet(message, key, &tk);
                     let _ = db_tag::add(&vec![encode(packet.p_tag)]);
-                    keys.insert(*rid as u32, packet.tag_key); 
-                    recursive_mock_send(&(*rid as u32), &packet.tag_key, message, expl_user, edge_list, map_id_ik, keys);
+                    keys.insert(Uid::from(*rid), packet.tag_key); 
+                    recursive_mock_send(&Uid::from(*rid), &packet.tag_key, message, expl_user, edge_list, map_id_ik, keys);
                 })
             },
             true => ()
         }
     }
 
-    fn write_vec_edges_to_file(edges: &Vec<(usize,usize)>, out_dir: &String) {
+    fn write_vec_edges_to_file(edges: &Vec<(usize,usize)>, out_dir: &String, seed: &SimSeed) {
         let mut file = File::create(out_dir).unwrap();
+        let _ = writeln!(file, "# {}", seed.header());
         for (snd, rcv) in edges {
             let output = format!("{},{}\n", snd, rcv);
             let _ = file.write_all(&output.as_bytes());
@@ -176,7 +423,7 @@ pub mod rwc_eval {
 
 #[cfg(test)]
 mod tests {
-    use crate::{simulation::utils::import_graph, rwc_eval::rwc_eval::eval_fuzz_trace_runtime, db::{db_tag, db_ik, db_nbr}};
+    use crate::{simulation::{utils::{import_graph, SimSeed}, spread::{Sir, SeedSelection}, adversary::{Adversaries, Behaviour}}, rwc_eval::rwc_eval::{eval_spec, eval_seed_recall, eval_adversary, Campaign, Forwarding}, db::{db_tag, db_ik, db_nbr}, experiment::spec::Spec};
 
     fn db_clear() {
         db_nbr::clear();
@@ -186,26 +433,67 @@ mod tests {
 
     #[test]
     fn test_trace_time() {
-        let (file_dir, st_node, _, _, _) = super::rwc_eval::select_dataset(&super::rwc_eval::Dataset::CollegeIM);
+        let spec = Spec::load("experiments/trace_time.toml").unwrap();
+        let seed = spec.sim_seed();
+        println!("{}", seed.header());
+
+        for (model, record) in eval_spec(&spec, &seed).unwrap() {
+            println!("{:?}; Fwd-Fuzz: ({}:{}:{})-({}:{}:{}); Runtime: {}", model, record[0], record[1], record[2], record[3], record[4], record[5], record[6]);
+        }
+    }
+
+    #[test]
+    fn test_lookup_latency() {
+        let spec = Spec::load("experiments/lookup_latency.toml").unwrap();
+        let seed = spec.sim_seed();
+        println!("{}", seed.header());
+
+        for (model, record) in eval_spec(&spec, &seed).unwrap() {
+            println!("{:?}; Rounds: {}; Users: {}; Lookup split: {}ms; fused: {}ms", model, record[0], record[1], record[2], record[3]);
+        }
+    }
+
+    #[test]
+    fn test_seed_recall() {
+        let (file_dir, _, round, _, _) = super::rwc_eval::select_dataset(&super::rwc_eval::Dataset::CollegeIM);
         let sys_graph = import_graph(file_dir);
-        // s2i: 0.05, i2r: 0.4-0.9; s2i: 0.03-0.08, i2r: 0.7;
-        let s2i_list = vec![vec![0.05], vec![0.03, 0.04, 0.05, 0.06, 0.07, 0.08]];
-        let i2r_list = vec![vec![0.4, 0.5, 0.6, 0.7, 0.8, 0.9], vec![0.7]];
         let trace_fpr: f32 = 0.01;
         let loop_index = 1;
+        let seed = SimSeed::from_env();
+        println!("{}", seed.header());
+        let model = Sir { s2i: 0.05, i2r: 0.6 };
+        let forwarding = Forwarding { model: &model, round, min_edges: 50 };
+        let selections = vec![SeedSelection::Random(5), SeedSelection::HighestDegree(5), SeedSelection::Clustered { count: 5, radius: 2 }];
 
-        let mut count = 0;
-        for b in 0..2 {
-            for s2i in s2i_list.get(b).unwrap() {
-                for i2r in i2r_list.get(b).unwrap() {
-                    db_clear();
-                    let output_dir = format!("./output/rwc/{}", count);
-                    let record = eval_fuzz_trace_runtime(&trace_fpr, &st_node, s2i, i2r, &loop_index, &sys_graph, &output_dir);
-                    println!("S-I-R: {}-{}; Fwd-Fuzz: ({}:{}:{})-({}:{}:{}); Runtime: {}", s2i, i2r, record[0], record[1], record[2], record[3], record[4], record[5], record[6]);
-                    count += 1;
-                }
+        for (count, selection) in selections.into_iter().enumerate() {
+            for shared_sender in [false, true] {
+                db_clear();
+                let campaign = Campaign { selection: selection.clone(), shared_sender };
+                let output_dir = format!("./output/seed/{}-{}", count, shared_sender);
+                let record = eval_seed_recall(&trace_fpr, &campaign, &forwarding, &seed, &loop_index, &sys_graph, &output_dir).unwrap();
+                println!("{:?}; Seeds: {}; Reached: {}; Fuzzy traced: {}; Traced: {}", campaign, record[0], record[1], record[2], record[3]);
             }
         }
         db_clear();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_adversary() {
+        let (file_dir, st_node, round, _, _) = super::rwc_eval::select_dataset(&super::rwc_eval::Dataset::CollegeIM);
+        let sys_graph = import_graph(file_dir);
+        let trace_fpr: f32 = 0.01;
+        let loop_index = 5;
+        let seed = SimSeed::from_env();
+        println!("{}", seed.header());
+        let model = Sir { s2i: 0.05, i2r: 0.6 };
+        let forwarding = Forwarding { model: &model, round, min_edges: 50 };
+        // the first one is the honest baseline
+        let behaviours = vec![(Behaviour::Retype, 0.0), (Behaviour::OffPlatform, 0.05), (Behaviour::Retype, 0.05), (Behaviour::FalseReport, 0.05), (Behaviour::Sybil(3), 0.05)];
+
+        for (behaviour, fraction) in behaviours {
+            let adversaries = Adversaries { behaviour, fraction };
+            let record = eval_adversary(&trace_fpr, &adversaries, &st_node, &forwarding, &seed, &loop_index, &sys_graph).unwrap();
+            println!("{:?}; Forwards: {}; Traceable: {}; Traced: {}; Recall: {}; Seed found: {}; Fuzzy value reached: {}, unreached: {}", adversaries, record[0], record[1], record[2], record[3], record[4], record[5], record[6]);
+        }
+    }
+}
@@ -6,17 +6,97 @@ pub mod utils {
     use petgraph::{graph::{NodeIndex, Graph}, visit::IntoNodeReferences, Undirected};
     use petgraph::dot::{Dot,Config};
     use petgraph::prelude::UnGraph;
-    use std::{io::{prelude::*, BufReader}, collections::HashMap, fs::File};
+    use std::{io::{prelude::*, BufReader}, collections::{HashMap, HashSet, hash_map::Entry}, fs::File};
     use std::fs;
 
-    use crate::tool::utils::hash;
+    use dotenv::dotenv;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use std::env;
 
-    pub fn rand_state (prob: &f32) -> bool {
+    use crate::tool::utils::hash_array_32;
+    use super::influence::{Influence, InfRecord};
+
+    // every stochastic step of a simulation draws from one of these
+    pub type SimRng = StdRng;
+
+    // an experiment seed, split into independent streams per trial
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SimSeed {
+        pub seed: u64,
+        pub trial: u64,
+    }
+
+    impl SimSeed {
+        pub fn new(seed: u64) -> Self {
+            SimSeed { seed, trial: 0 }
+        }
+
+        // SIM_SEED and SIM_TRIAL from .env or the environment, a fresh seed if SIM_SEED is unset
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            let seed = env::var("SIM_SEED").map(|s| s.trim().parse::<u64>().expect("SIM_SEED is not a u64.")).unwrap_or_else(|_| rand::random::<u64>());
+            let trial = env::var("SIM_TRIAL").map(|s| s.trim().parse::<u64>().expect("SIM_TRIAL is not a u64.")).unwrap_or(0);
+            SimSeed { seed, trial }
+        }
+
+        pub fn trial(&self, trial: u64) -> Self {
+            SimSeed { seed: self.seed, trial }
+        }
+
+        pub fn rng(&self) -> SimRng {
+            SimRng::from_seed(hash_array_32(format!("{},{}", self.seed, self.trial).as_bytes()))
+        }
+
+        // first line of every output file
+        pub fn header(&self) -> String {
+            format!("seed: {}, trial: {}", self.seed, self.trial)
+        }
+    }
+
+    pub fn rand_state (prob: &f32, rng: &mut SimRng) -> bool {
         let threshold: u32 = (prob * 1000.0) as u32;
-        let coin = rand::random::<u32>() % 1000;
+        let coin = rng.gen::<u32>() % 1000;
         return coin < threshold
     }
 
+    // edges in the order they were added, with a hashed index for constant time lookups
+    #[derive(Clone, Debug, Default)]
+    pub struct EdgeSet {
+        edges: Vec<(usize,usize)>,
+        index: HashSet<(usize,usize)>,
+    }
+
+    impl EdgeSet {
+        pub fn new() -> Self {
+            EdgeSet::default()
+        }
+
+        pub fn contains(&self, edge: &(usize,usize)) -> bool {
+            self.index.contains(edge)
+        }
+
+        // returns false if the edge was already there
+        pub fn push(&mut self, edge: (usize,usize)) -> bool {
+            self.index.insert(edge).then(|| self.edges.push(edge)).is_some()
+        }
+
+        pub fn len(&self) -> usize {
+            self.edges.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.edges.is_empty()
+        }
+
+        pub fn as_vec(&self) -> &Vec<(usize,usize)> {
+            &self.edges
+        }
+
+        pub fn into_vec(self) -> Vec<(usize,usize)> {
+            self.edges
+        }
+    }
+
     pub fn import_graph(file_dir: String) -> UnGraph::<usize, ()> {
         let file = fs::File::open(file_dir).unwrap();
         let reader = BufReader::new(file);
@@ -49,32 +129,29 @@ pub mod utils {
         }
     }
 
+    // keeps the first of (a, b) and (b, a)
     pub fn dedup_vec_edges (list: &Vec<(usize,usize)>) -> Vec<(usize,usize)> {
-        let mut exist_edge = Vec::<(usize,usize)>::new();
-        let mut contained_edges = Vec::<[u8;16]>::new();
-        list.iter().for_each(|e| {
-            let tag_1 = hash(&(e.0.to_string() + &e.1.to_string()));
-            let tag_2 = hash(&(e.1.to_string() + &e.0.to_string()));
-            if !(contained_edges.contains(&tag_1)&contained_edges.contains(&tag_2)) {
-                contained_edges.push(tag_1);
-                contained_edges.push(tag_2);
-                exist_edge.push(*e);
+        let mut contained_edges = HashSet::<(usize,usize)>::new();
+        list.iter()
+            .filter(|e| contained_edges.insert((e.0.min(e.1), e.0.max(e.1))))
+            .copied()
+            .collect()
+    }
+
+    // nodes in the order they first appear, each added once
+    fn add_nodes<W>(graph: &mut Graph::<usize,W>, node_index: &mut HashMap::<usize, NodeIndex>, nodes: impl Iterator<Item = usize>) {
+        nodes.for_each(|n| {
+            if let Entry::Vacant(entry) = node_index.entry(n) {
+                entry.insert(graph.add_node(n));
             }
         });
-        exist_edge
     }
 
     pub fn vec_to_graph (edges: &Vec::<(usize,usize)>) -> (Graph::<usize,()>, HashMap::<usize, NodeIndex>) {
-        let mut nodes = Vec::<usize>::new();
         let mut node_index = HashMap::<usize, NodeIndex>::new();
-        let mut fwd_graph = Graph::<usize,()>::new();
-
-        for e in edges {
-           (!nodes.contains(&e.0)).then(|| nodes.push(e.0));
-           (!nodes.contains(&e.1)).then(|| nodes.push(e.1));
-        }
+        let mut fwd_graph = Graph::<usize,()>::with_capacity(edges.len() + 1, edges.len());
 
-        nodes.iter().for_each(|n| { node_index.insert(*n, fwd_graph.add_node(*n as usize)); });
+        add_nodes(&mut fwd_graph, &mut node_index, edges.iter().flat_map(|e| [e.0, e.1]));
 
         for e in edges {
             fwd_graph.add_edge(*node_index.get(&e.0).unwrap(), *node_index.get(&e.1).unwrap(), ());
@@ -84,15 +161,9 @@ pub mod utils {
 
     pub fn hmap_to_graph (hmap_edges: &HashMap<(usize,usize),(usize,usize)>) -> (Graph::<usize,usize>, HashMap::<usize, NodeIndex>) {
         let mut node_index = HashMap::<usize, NodeIndex>::new();
-        let mut fwd_graph = Graph::<usize,usize>::new();
-        let mut nodes = Vec::<usize>::new();
+        let mut fwd_graph = Graph::<usize,usize>::with_capacity(hmap_edges.len() + 1, hmap_edges.len());
 
-        hmap_edges.iter().for_each(|e| {
-            (!nodes.contains(&e.0.0)).then(|| nodes.push(e.0.0));
-            (!nodes.contains(&e.0.1)).then(|| nodes.push(e.0.1));
-        });
-
-        nodes.iter().for_each(|n| { node_index.insert(*n, fwd_graph.add_node(*n as usize)); });
+        add_nodes(&mut fwd_graph, &mut node_index, hmap_edges.keys().flat_map(|e| [e.0, e.1]));
 
         hmap_edges.iter().for_each(|e| {
             fwd_graph.add_edge(*node_index.get(&e.0.0).unwrap(), *node_index.get(&e.0.1).unwrap(), e.1.0);
@@ -121,9 +192,9 @@ pub mod utils {
         fuzzy_graph
     }
 
-    pub fn graph_to_dot<T: std::fmt::Debug, U: std::fmt::Debug> (g: &Graph<T, U>, dir: String) {
+    pub fn graph_to_dot<T: std::fmt::Debug, U: std::fmt::Debug> (g: &Graph<T, U>, dir: String, seed: &SimSeed) {
         let mut f = File::create(dir).unwrap();
-        let output = format!("{:?}", Dot::with_config(g, &[]));
+        let output = format!("// {}\n{:?}", seed.header(), Dot::with_config(g, &[]));
         let _ = f.write_all(&output.as_bytes());
     }
 
@@ -135,7 +206,7 @@ pub mod utils {
         id_wt_map
     }
 
-    pub fn graph_to_dot_for_draw(fwd_edge_list: &Vec<(usize,usize)>, fuzz_edge_list: &HashMap<(usize, usize), (usize, usize)>, sys_graph: &UnGraph<usize, ()>, dir: &String) {
+    pub fn graph_to_dot_for_draw(fwd_edge_list: &[(usize,usize)], fuzz_edge_list: &HashMap<(usize, usize), (usize, usize)>, sys_graph: &UnGraph<usize, ()>, dir: &String, seed: &SimSeed) {
         let mut wt_graph = Graph::<usize, usize>::new();
         sys_graph.node_indices().for_each(|node| {
             wt_graph.add_node(node.index());
@@ -159,20 +230,22 @@ pub mod utils {
         let ug: UnGraph<usize,usize> = wt_graph.into_edge_type::<Undirected>();
 
         let mut f = File::create(dir).unwrap();
-        let output = format!("{:?}", Dot::with_config(&ug, &[Config::NodeNoLabel]));
+        let output = format!("// {}\n{:?}", seed.header(), Dot::with_config(&ug, &[Config::NodeNoLabel]));
         let _ = f.write_all(&output.as_bytes());
     }
 
-    pub fn write_val_to_file<T: std::fmt::Debug> (node_value: &HashMap<usize,T>, dir: String) {
+    pub fn write_val_to_file<T: std::fmt::Debug> (node_value: &HashMap<usize,T>, dir: String, seed: &SimSeed) {
         let mut f = File::create(dir).unwrap();
+        let _ = writeln!(f, "# {}", seed.header());
         for (k,v) in node_value {
             let output = format!("{},{:?}\n", k, v);
             let _ = f.write_all(&output.as_bytes());
         }
     }
 
-    pub fn gen_raw_data_file (node_tpr: &HashMap<usize,f64>, real_id_map: &HashMap<usize, NodeIndex>, dir: String) {
+    pub fn gen_raw_data_file (node_tpr: &HashMap<usize,f64>, real_id_map: &HashMap<usize, NodeIndex>, dir: String, seed: &SimSeed) {
         let mut f = File::create(dir).unwrap();
+        let _ = writeln!(f, "# {}", seed.header());
         for (node_id,fuzz_val) in node_tpr {
             let is_true_positive: usize = match real_id_map.contains_key(node_id) {
                 true => 1,
@@ -182,16 +255,37 @@ pub mod utils {
             let _ = f.write_all(&output.as_bytes());
         }
     }
+
+    // the csv the analysis reads, in the layout of `influence::Influence::records`
+    pub fn gen_inf_csv_file (records: &[InfRecord], metric: &Influence, dir: String, seed: &SimSeed) {
+        let mut f = File::create(dir).unwrap();
+        let _ = writeln!(f, "# {}", seed.header());
+        let _ = writeln!(f, "id,is_true_positive,fuzzy_value,{}", metric.name());
+        for (node_id, is_true_positive, fuzz_val, inf) in records {
+            let _ = writeln!(f, "{},{},{},{}", node_id, is_true_positive, fuzz_val, inf);
+        }
+    }
+
+    // one line (node_id, seed it got the message from, traced value) per reached node, 0 when it was not traced
+    pub fn gen_seed_data_file (origin: &HashMap<usize,usize>, node_val: &HashMap<usize,f64>, dir: String, seed: &SimSeed) {
+        let mut f = File::create(dir).unwrap();
+        let _ = writeln!(f, "# {}", seed.header());
+        let mut nodes: Vec<(&usize, &usize)> = origin.iter().collect();
+        nodes.sort();
+        for (node_id, src_seed) in nodes {
+            let _ = writeln!(f, "{},{},{}", node_id, src_seed, node_val.get(node_id).unwrap_or(&0.0));
+        }
+    }
 }
 
 pub mod sir {
     extern crate petgraph;
     
-    use petgraph::{graph::NodeIndex, visit::IntoNodeReferences};
+    use petgraph::graph::NodeIndex;
     use petgraph::prelude::UnGraph;
     use std::collections::HashMap;
 
-    use super::utils::rand_state;
+    use super::utils::{rand_state, EdgeSet, SimRng};
 
     #[derive(Debug,PartialEq)]
     enum Condition {
@@ -200,55 +294,68 @@ pub mod sir {
         Recovered,
     }
 
-    pub fn sir_spread(round: &usize, st_node: &usize, s2i: &f32, i2r: &f32, sys_graph: &UnGraph::<usize, ()>) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
-        // initialization
-        let mut nodes_state = HashMap::<NodeIndex, Condition>::new();
-        let mut edges_state = Vec::<(usize,usize)>::new();
+    pub fn sir_spread(round: &usize, st_node: &usize, s2i: &f32, i2r: &f32, sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+        sir_spread_seeds(round, &[*st_node], s2i, i2r, sys_graph, rng)
+    }
+
+    // every seed starts infective, with usize::MAX as its source
+    pub fn sir_spread_seeds(round: &usize, st_nodes: &[usize], s2i: &f32, i2r: &f32, sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+        // initialization, states are indexed by node
+        let mut nodes_state: Vec<Condition> = sys_graph.node_indices().map(|_| Condition::Susceptible).collect();
+        let mut edges_state = EdgeSet::new();
         let mut node_msg_source = HashMap::<usize,usize>::new();
-        for n in sys_graph.node_references() {
-            nodes_state.insert(n.0, Condition::Susceptible);
-        }
-        let start_index = NodeIndex::new(*st_node);
-        if let Some(x) = nodes_state.get_mut(&start_index) {
-            *x = Condition::Infective;
+        let mut infected_nodes: Vec<NodeIndex> = Vec::new();
+        for st_node in st_nodes {
+            let start_index = NodeIndex::new(*st_node);
+            if let Some(x) = nodes_state.get_mut(start_index.index()) {
+                (*x == Condition::Susceptible).then(|| infected_nodes.push(start_index));
+                *x = Condition::Infective;
+            }
+            node_msg_source.insert(start_index.index(), usize::MAX);
         }
-        node_msg_source.insert(start_index.index(), usize::MAX);
+        infected_nodes.sort();
         // Infective, recover or infect others
         for _t in 0..*round {
             let mut tbd_nodes = Vec::<NodeIndex>::new();
-            let infected_nodes: Vec<NodeIndex> = sys_graph.node_indices().filter(|n| *(nodes_state.get(&n).unwrap()) == Condition::Infective).collect();
             infected_nodes.iter().for_each(|n| {
                 // Infect neighbors
                 for nbr in sys_graph.neighbors(*n) {
                     let mut is_infected = false;
-                    match *nodes_state.get(&nbr).unwrap() {
+                    match nodes_state[nbr.index()] {
                         Condition::Susceptible => {
-                            rand_state(s2i).then(||{
-                                tbd_nodes.push(nbr.clone());
+                            rand_state(s2i, rng).then(||{
+                                tbd_nodes.push(nbr);
                                 is_infected = true;
                             });
                         },
                         _ => {
-                            vec_edge_exists(&true, &edges_state, &nbr, &n).then(|| is_infected = rand_state(s2i));
+                            vec_edge_exists(&true, &edges_state, &nbr, n).then(|| is_infected = rand_state(s2i, rng));
                         },
                     }
 
-                    (is_infected & vec_edge_exists(&true, &edges_state, &n, &nbr)).then(|| {
+                    (is_infected & vec_edge_exists(&true, &edges_state, n, &nbr)).then(|| {
                         edges_state.push((n.index(), nbr.index()));
-                        (!node_msg_source.contains_key(&nbr.index())).then(|| {
-                            node_msg_source.insert(nbr.index(), n.index());
-                        });
+                        node_msg_source.entry(nbr.index()).or_insert(n.index());
                     });
                 }
                 // Recover?
-                rand_state(i2r).then(|| *(nodes_state.get_mut(&n).unwrap()) = Condition::Recovered);
+                rand_state(i2r, rng).then(|| nodes_state[n.index()] = Condition::Recovered);
             });
-            tbd_nodes.iter().for_each(|&x| *(nodes_state.get_mut(&x).unwrap()) = Condition::Infective);
+            infected_nodes.retain(|n| nodes_state[n.index()] == Condition::Infective);
+            // a node infected twice in a round is listed once
+            for x in tbd_nodes {
+                if nodes_state[x.index()] == Condition::Susceptible {
+                    nodes_state[x.index()] = Condition::Infective;
+                    infected_nodes.push(x);
+                }
+            }
+            infected_nodes.sort();
         }
-        (edges_state, node_msg_source)
+        (edges_state.into_vec(), node_msg_source)
     }
 
-    pub fn vec_edge_exists(is_directed: &bool, edges_state: &Vec<(usize, usize)>, snd: &NodeIndex, rcv: &NodeIndex) -> bool {
+    // true if the edge (or with is_directed false, either direction of it) is not in edges_state
+    pub fn vec_edge_exists(is_directed: &bool, edges_state: &EdgeSet, snd: &NodeIndex, rcv: &NodeIndex) -> bool {
         match is_directed {
             true => !edges_state.contains(&(snd.index(), rcv.index())),
             false => !edges_state.contains(&(snd.index(), rcv.index())) & !edges_state.contains(&(rcv.index(), snd.index())),
@@ -257,26 +364,859 @@ pub mod sir {
 
 }
 
+// Diffusion models that all produce the forwarded edges and the first sender of every reached node,
+// in the shape of `sir::sir_spread`, so the evaluation can swap them freely.
+pub mod spread {
+    extern crate petgraph;
+
+    use petgraph::graph::NodeIndex;
+    use petgraph::prelude::UnGraph;
+    use rand::{Rng, seq::index};
+    use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    use super::{utils::{rand_state, EdgeSet, SimRng}, sir::{sir_spread_seeds, vec_edge_exists}, temporal::{TemporalGraph, TemporalRate}};
+
+    pub trait SpreadModel {
+        // (forwarded edges, node -> the node it first got the message from, usize::MAX for every seed)
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>);
+
+        fn spread(&self, round: &usize, st_node: &usize, sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            self.spread_seeds(round, &[*st_node], sys_graph, rng)
+        }
+    }
+
+    // how the accounts that first post the message are picked
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum SeedSelection {
+        Fixed(Vec<usize>),
+        // drawn uniformly
+        Random(usize),
+        // the users of highest degree
+        HighestDegree(usize),
+        // drawn from the users within `radius` hops of a random user, i.e. one coordinated cluster
+        Clustered { count: usize, radius: usize },
+    }
+
+    impl SeedSelection {
+        // sorted and without repeats
+        pub fn select(&self, sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> Vec<usize> {
+            let n = sys_graph.node_count();
+            let mut seeds: Vec<usize> = match self {
+                SeedSelection::Fixed(seeds) => seeds.clone(),
+                SeedSelection::Random(count) => index::sample(rng, n, (*count).min(n)).into_vec(),
+                SeedSelection::HighestDegree(count) => {
+                    let mut nodes: Vec<(usize, usize)> = sys_graph.node_indices().map(|v| (sys_graph.neighbors(v).count(), v.index())).collect();
+                    nodes.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+                    nodes.into_iter().take(*count).map(|(_, v)| v).collect()
+                },
+                SeedSelection::Clustered { count, radius } => {
+                    if n == 0 {
+                        return Vec::new();
+                    }
+                    let centre = NodeIndex::new(rng.gen_range(0..n));
+                    let mut ball: Vec<usize> = vec![centre.index()];
+                    let mut visited: HashSet<NodeIndex> = HashSet::from([centre]);
+                    let mut frontier = vec![centre];
+                    for _hop in 0..*radius {
+                        frontier = frontier.iter().flat_map(|v| sys_graph.neighbors(*v)).filter(|v| visited.insert(*v)).collect();
+                        ball.extend(frontier.iter().map(|v| v.index()));
+                    }
+                    ball.sort();
+                    index::sample(rng, ball.len(), (*count).min(ball.len())).into_iter().map(|i| ball[i]).collect()
+                },
+            };
+            seeds.sort();
+            seeds.dedup();
+            seeds
+        }
+    }
+
+    // the seed every reached node got the message from, by following first senders back
+    pub fn origin_of(node_msg_source: &HashMap<usize,usize>) -> HashMap<usize,usize> {
+        let mut origin = HashMap::<usize,usize>::new();
+        for node in node_msg_source.keys() {
+            let mut path = vec![*node];
+            let seed = loop {
+                let last = *path.last().unwrap();
+                match (origin.get(&last), node_msg_source.get(&last)) {
+                    (Some(seed), _) => break *seed,
+                    (None, Some(&usize::MAX)) => break last,
+                    (None, Some(src)) if !path.contains(src) => path.push(*src),
+                    // a source chain that does not end at a seed
+                    _ => break usize::MAX,
+                }
+            };
+            path.into_iter().for_each(|v| { origin.insert(v, seed); });
+        }
+        origin
+    }
+
+    // records a forward unless the pair already exchanged the message
+    fn forward(edges_state: &mut EdgeSet, node_msg_source: &mut HashMap<usize,usize>, snd: &NodeIndex, rcv: &NodeIndex) {
+        vec_edge_exists(&false, edges_state, snd, rcv).then(|| {
+            edges_state.push((snd.index(), rcv.index()));
+            node_msg_source.entry(rcv.index()).or_insert(snd.index());
+        });
+    }
+
+    fn start(st_nodes: &[usize]) -> (EdgeSet, HashMap<usize,usize>) {
+        (EdgeSet::new(), st_nodes.iter().map(|n| (*n, usize::MAX)).collect())
+    }
+
+    fn seed_indices(st_nodes: &[usize]) -> Vec<NodeIndex> {
+        st_nodes.iter().map(|n| NodeIndex::new(*n)).collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct Sir {
+        pub s2i: f32,
+        pub i2r: f32,
+    }
+
+    impl SpreadModel for Sir {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            sir_spread_seeds(round, st_nodes, &self.s2i, &self.i2r, sys_graph, rng)
+        }
+    }
+
+    // infective users stop forwarding with i2s, and may be infected again later
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct Sis {
+        pub s2i: f32,
+        pub i2s: f32,
+    }
+
+    impl SpreadModel for Sis {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            let (mut edges_state, mut node_msg_source) = start(st_nodes);
+            let mut infective: BTreeSet<NodeIndex> = st_nodes.iter().map(|n| NodeIndex::new(*n)).collect();
+            for _t in 0..*round {
+                let mut next = infective.clone();
+                for n in &infective {
+                    for nbr in sys_graph.neighbors(*n) {
+                        rand_state(&self.s2i, rng).then(|| {
+                            forward(&mut edges_state, &mut node_msg_source, n, &nbr);
+                            next.insert(nbr);
+                        });
+                    }
+                    rand_state(&self.i2s, rng).then(|| next.remove(n));
+                }
+                infective = next;
+            }
+            (edges_state.into_vec(), node_msg_source)
+        }
+    }
+
+    // exposed users hold the message but only forward it once they turn infective with e2i
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct Seir {
+        pub s2e: f32,
+        pub e2i: f32,
+        pub i2r: f32,
+    }
+
+    impl SpreadModel for Seir {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            let (mut edges_state, mut node_msg_source) = start(st_nodes);
+            let mut exposed: Vec<NodeIndex> = Vec::new();
+            let mut infective: Vec<NodeIndex> = seed_indices(st_nodes);
+            for _t in 0..*round {
+                let (mut next_exposed, mut next_infective) = (Vec::new(), Vec::new());
+                for e in exposed {
+                    match rand_state(&self.e2i, rng) {
+                        true => next_infective.push(e),
+                        false => next_exposed.push(e),
+                    }
+                }
+                for n in &infective {
+                    for nbr in sys_graph.neighbors(*n) {
+                        let susceptible = !node_msg_source.contains_key(&nbr.index());
+                        rand_state(&self.s2e, rng).then(|| {
+                            forward(&mut edges_state, &mut node_msg_source, n, &nbr);
+                            susceptible.then(|| next_exposed.push(nbr));
+                        });
+                    }
+                    (!rand_state(&self.i2r, rng)).then(|| next_infective.push(*n));
+                }
+                (exposed, infective) = (next_exposed, next_infective);
+            }
+            (edges_state.into_vec(), node_msg_source)
+        }
+    }
+
+    // every newly reached user gets one chance to pass the message to each nbr
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct IndependentCascade {
+        pub p: f32,
+    }
+
+    impl SpreadModel for IndependentCascade {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            let (mut edges_state, mut node_msg_source) = start(st_nodes);
+            let mut frontier: Vec<NodeIndex> = seed_indices(st_nodes);
+            for _t in 0..*round {
+                let mut next = Vec::new();
+                for n in &frontier {
+                    for nbr in sys_graph.neighbors(*n) {
+                        if !node_msg_source.contains_key(&nbr.index()) && rand_state(&self.p, rng) {
+                            forward(&mut edges_state, &mut node_msg_source, n, &nbr);
+                            next.push(nbr);
+                        }
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                frontier = next;
+            }
+            (edges_state.into_vec(), node_msg_source)
+        }
+    }
+
+    // a user forwards once the share of its nbrs holding the message reaches its own random threshold,
+    // and it gets the message from each of them
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct LinearThreshold;
+
+    impl SpreadModel for LinearThreshold {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            let (mut edges_state, mut node_msg_source) = start(st_nodes);
+            let threshold: HashMap<NodeIndex, f32> = sys_graph.node_indices().map(|n| (n, rng.gen::<f32>())).collect();
+            for _t in 0..*round {
+                let mut activated: Vec<(NodeIndex, Vec<NodeIndex>)> = Vec::new();
+                for n in sys_graph.node_indices().filter(|n| !node_msg_source.contains_key(&n.index())) {
+                    let active: Vec<NodeIndex> = sys_graph.neighbors(n).filter(|nbr| node_msg_source.contains_key(&nbr.index())).collect();
+                    let degree = sys_graph.neighbors(n).count();
+                    (!active.is_empty() && active.len() as f32 / degree as f32 >= threshold[&n]).then(|| activated.push((n, active)));
+                }
+                if activated.is_empty() {
+                    break;
+                }
+                for (n, active) in activated {
+                    active.iter().for_each(|nbr| forward(&mut edges_state, &mut node_msg_source, nbr, &n));
+                }
+            }
+            (edges_state.into_vec(), node_msg_source)
+        }
+    }
+
+    // one of the models above, as an experiment spec names it, e.g. { kind = "sir", s2i = 0.05, i2r = 0.6 }
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    pub enum Model {
+        Sir(Sir),
+        Sis(Sis),
+        Seir(Seir),
+        IndependentCascade(IndependentCascade),
+        LinearThreshold,
+        // needs the contacts of a temporal graph, see `TemporalRate::over`
+        Temporal(TemporalRate),
+    }
+
+    impl Model {
+        // A temporal model spreads over the contacts, any other over the system graph.
+        // None for a temporal model without contacts
+        pub fn over<'a>(&self, contacts: Option<&'a TemporalGraph>) -> Option<Box<dyn SpreadModel + 'a>> {
+            match *self {
+                Model::Sir(model) => Some(Box::new(model)),
+                Model::Sis(model) => Some(Box::new(model)),
+                Model::Seir(model) => Some(Box::new(model)),
+                Model::IndependentCascade(model) => Some(Box::new(model)),
+                Model::LinearThreshold => Some(Box::new(LinearThreshold)),
+                Model::Temporal(rate) => contacts.map(|contacts| Box::new(rate.over(contacts)) as Box<dyn SpreadModel + 'a>),
+            }
+        }
+    }
+}
+
+// Temporal networks, where a message can only cross a contact at or after the time it happens.
+pub mod temporal {
+    extern crate petgraph;
+
+    use petgraph::prelude::UnGraph;
+    use serde::{Deserialize, Serialize};
+    use std::collections::{HashMap, HashSet, hash_map::Entry};
+    use std::{fs, io::{BufRead, BufReader}};
+
+    use super::{spread::SpreadModel, utils::{rand_state, EdgeSet, SimRng}};
+
+    // undirected contacts (u, v, time), sorted by time
+    #[derive(Clone, Debug)]
+    pub struct TemporalGraph {
+        pub contacts: Vec<(usize, usize, u64)>,
+    }
+
+    impl TemporalGraph {
+        pub fn from_contacts(mut contacts: Vec<(usize, usize, u64)>) -> Self {
+            contacts.sort_by_key(|(_, _, t)| *t);
+            TemporalGraph { contacts }
+        }
+
+        // every pair that is ever in contact, as the system graph for tracing
+        pub fn static_graph(&self) -> UnGraph::<usize, ()> {
+            let mut pairs: Vec<(u32, u32)> = self.contacts.iter()
+                .map(|(u, v, _)| ((*u).min(*v) as u32, (*u).max(*v) as u32))
+                .collect::<HashSet<(u32, u32)>>()
+                .into_iter().collect();
+            pairs.sort();
+            UnGraph::<usize, ()>::from_edges(pairs)
+        }
+
+        pub fn first_contact(&self, node: &usize) -> Option<u64> {
+            self.contacts.iter().find(|(u, v, _)| (u == node) | (v == node)).map(|(_, _, t)| *t)
+        }
+    }
+
+    // reads "source,destination,time" lines, commas or whitespace as separators
+    pub fn import_temporal_graph(file_dir: String) -> TemporalGraph {
+        let file = fs::File::open(file_dir).unwrap();
+        let reader = BufReader::new(file);
+        let mut contacts = Vec::<(usize, usize, u64)>::new();
+        for line in reader.lines() {
+            let str_line = line.unwrap();
+            let items: Vec<&str> = str_line.split(|c: char| (c == ',') | c.is_whitespace()).filter(|item| !item.is_empty()).collect();
+            if items.is_empty() {
+                continue;
+            }
+            let time = items.get(2).expect("a temporal edge needs a time column").parse::<u64>().unwrap();
+            contacts.push((items[0].parse::<usize>().unwrap(), items[1].parse::<usize>().unwrap(), time));
+        }
+        TemporalGraph::from_contacts(contacts)
+    }
+
+    // (forwarded edges, first sender of every reached node, time every node got the message)
+    pub type TemporalSpread = (Vec<(usize,usize)>, HashMap<usize,usize>, HashMap<usize,u64>);
+
+    // a user holding the message forwards it over each of its contacts with s2i, for `period` seconds after receiving it
+    #[derive(Clone, Debug)]
+    pub struct TemporalSir<'a> {
+        pub graph: &'a TemporalGraph,
+        pub s2i: f32,
+        pub period: u64,
+    }
+
+    // the parameters of a TemporalSir, as an experiment spec names them, e.g. { kind = "temporal", s2i = 0.03, period = 86400 }
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub struct TemporalRate {
+        pub s2i: f32,
+        pub period: u64,
+    }
+
+    impl TemporalRate {
+        pub fn over<'a>(&self, graph: &'a TemporalGraph) -> TemporalSir<'a> {
+            TemporalSir { graph, s2i: self.s2i, period: self.period }
+        }
+    }
+
+    impl TemporalSir<'_> {
+        pub fn spread_from(&self, max_hop: &usize, st_node: &usize, st_time: &u64, rng: &mut SimRng) -> TemporalSpread {
+            self.spread_from_seeds(max_hop, &[(*st_node, *st_time)], rng)
+        }
+
+        // every seed posts the message at its own time
+        pub fn spread_from_seeds(&self, max_hop: &usize, starts: &[(usize, u64)], rng: &mut SimRng) -> TemporalSpread {
+            let mut edges_state = EdgeSet::new();
+            let mut node_msg_source: HashMap<usize,usize> = starts.iter().map(|(n, _)| (*n, usize::MAX)).collect();
+            let mut recv_time: HashMap<usize,u64> = starts.iter().map(|(n, t)| (*n, *t)).collect();
+            let mut hop: HashMap<usize,usize> = starts.iter().map(|(n, _)| (*n, 0)).collect();
+            let st_time = starts.iter().map(|(_, t)| *t).min().unwrap_or(0);
+            for (u, v, t) in self.graph.contacts.iter().filter(|(_, _, t)| *t >= st_time) {
+                for (snd, rcv) in [(*u, *v), (*v, *u)] {
+                    let forwarding = match (recv_time.get(&snd), hop.get(&snd)) {
+                        // a relay cannot pass on a message within the contact time it got it, a seed posts at its own time
+                        (Some(t_recv), Some(h)) => ((*t_recv < *t) | ((*h == 0) & (*t_recv == *t))) & (*t <= t_recv + self.period) & (h < max_hop),
+                        _ => false,
+                    };
+                    let exchanged = edges_state.contains(&(snd, rcv)) | edges_state.contains(&(rcv, snd));
+                    if forwarding & !exchanged & rand_state(&self.s2i, rng) {
+                        edges_state.push((snd, rcv));
+                        if let Entry::Vacant(src) = node_msg_source.entry(rcv) {
+                            src.insert(snd);
+                            recv_time.insert(rcv, *t);
+                            hop.insert(rcv, hop[&snd] + 1);
+                        }
+                    }
+                }
+            }
+            (edges_state.into_vec(), node_msg_source, recv_time)
+        }
+    }
+
+    // each seed starts at its first contact, `round` bounds the hops from it and `sys_graph` should be `static_graph()`
+    impl SpreadModel for TemporalSir<'_> {
+        fn spread_seeds(&self, round: &usize, st_nodes: &[usize], _sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> (Vec::<(usize,usize)>, HashMap::<usize,usize>) {
+            let starts: Vec<(usize, u64)> = st_nodes.iter().map(|n| (*n, self.graph.first_contact(n).unwrap_or(0))).collect();
+            let (edges_state, node_msg_source, _) = self.spread_from_seeds(round, &starts, rng);
+            (edges_state, node_msg_source)
+        }
+    }
+}
+
+// Synthetic system graphs, node i has weight i and the same seed gives the same graph.
+pub mod generators {
+    extern crate petgraph;
+
+    use petgraph::{graph::NodeIndex, prelude::UnGraph};
+    use rand::{Rng, seq::SliceRandom};
+    use std::collections::HashSet;
+
+    use super::utils::SimSeed;
+
+    fn graph_from_pairs(n: usize, pairs: &HashSet<(usize, usize)>) -> UnGraph::<usize, ()> {
+        let mut graph = UnGraph::<usize, ()>::with_capacity(n, pairs.len());
+        (0..n).for_each(|i| { graph.add_node(i); });
+        let mut pairs: Vec<&(usize, usize)> = pairs.iter().collect();
+        pairs.sort();
+        pairs.into_iter().for_each(|(u, v)| { graph.add_edge(NodeIndex::new(*u), NodeIndex::new(*v), ()); });
+        graph
+    }
+
+    fn pair(u: usize, v: usize) -> (usize, usize) {
+        (u.min(v), u.max(v))
+    }
+
+    // every pair is linked with p, jumping over the skipped pairs with geometric gaps so sparse graphs take O(n + m)
+    pub fn erdos_renyi(n: &usize, p: &f32, seed: &SimSeed) -> UnGraph::<usize, ()> {
+        let mut rng = seed.rng();
+        let mut pairs = HashSet::new();
+        if *p <= 0.0 {
+            return graph_from_pairs(*n, &pairs);
+        }
+        let log_q = (1.0 - (*p as f64).min(1.0 - f64::EPSILON)).ln();
+        // walk the pairs (v, w) with w < v in order
+        let (mut v, mut w): (usize, i64) = (1, -1);
+        while v < *n {
+            w += 1 + ((1.0 - rng.gen::<f64>()).ln() / log_q).floor() as i64;
+            while (w >= v as i64) & (v < *n) {
+                w -= v as i64;
+                v += 1;
+            }
+            (v < *n).then(|| pairs.insert((w as usize, v)));
+        }
+        graph_from_pairs(*n, &pairs)
+    }
+
+    // preferential attachment, every new node links to m existing ones picked by degree
+    pub fn barabasi_albert(n: &usize, m: &usize, seed: &SimSeed) -> UnGraph::<usize, ()> {
+        assert!((1..*n).contains(m), "m must be in 1..n");
+        let mut rng = seed.rng();
+        let mut pairs = HashSet::new();
+        // one entry per edge end, so a uniform pick is proportional to degree
+        let mut ends: Vec<usize> = Vec::new();
+        for u in 0..=*m {
+            for v in (u + 1)..=*m {
+                pairs.insert((u, v));
+                ends.extend([u, v]);
+            }
+        }
+        for u in (*m + 1)..*n {
+            let mut targets = HashSet::new();
+            while targets.len() < *m {
+                targets.insert(*ends.choose(&mut rng).unwrap());
+            }
+            let mut targets: Vec<usize> = targets.into_iter().collect();
+            targets.sort();
+            for v in targets {
+                pairs.insert(pair(u, v));
+                ends.extend([u, v]);
+            }
+        }
+        graph_from_pairs(*n, &pairs)
+    }
+
+    // a ring where every node links to its k nearest nodes, each link rewired to a random node with beta
+    pub fn watts_strogatz(n: &usize, k: &usize, beta: &f32, seed: &SimSeed) -> UnGraph::<usize, ()> {
+        assert!(k.is_multiple_of(2) & (k < n), "k must be even and below n");
+        let mut rng = seed.rng();
+        let mut pairs = HashSet::new();
+        for u in 0..*n {
+            for j in 1..=(k / 2) {
+                pairs.insert(pair(u, (u + j) % n));
+            }
+        }
+        for j in 1..=(k / 2) {
+            for u in 0..*n {
+                let old = pair(u, (u + j) % n);
+                if (rng.gen::<f32>() < *beta) & pairs.contains(&old) {
+                    let v = rng.gen_range(0..*n);
+                    if (v != u) & !pairs.contains(&pair(u, v)) {
+                        pairs.remove(&old);
+                        pairs.insert(pair(u, v));
+                    }
+                }
+            }
+        }
+        graph_from_pairs(*n, &pairs)
+    }
+
+    // nodes split into blocks of `sizes`, a pair in blocks (a, b) is linked with probs[a][b]
+    pub fn stochastic_block(sizes: &[usize], probs: &[Vec<f32>], seed: &SimSeed) -> UnGraph::<usize, ()> {
+        assert!((probs.len() == sizes.len()) & probs.iter().all(|row| row.len() == sizes.len()), "probs must be a square matrix over the blocks");
+        let mut rng = seed.rng();
+        let block: Vec<usize> = sizes.iter().enumerate().flat_map(|(b, size)| std::iter::repeat_n(b, *size)).collect();
+        let mut pairs = HashSet::new();
+        for u in 0..block.len() {
+            for v in (u + 1)..block.len() {
+                (rng.gen::<f32>() < probs[block[u]][block[v]]).then(|| pairs.insert((u, v)));
+            }
+        }
+        graph_from_pairs(block.len(), &pairs)
+    }
+
+    // random matching of degree stubs, self loops and repeated links are dropped
+    pub fn configuration_model(degrees: &[usize], seed: &SimSeed) -> UnGraph::<usize, ()> {
+        assert!(degrees.iter().sum::<usize>().is_multiple_of(2), "the degree sum must be even");
+        let mut rng = seed.rng();
+        let mut stubs: Vec<usize> = degrees.iter().enumerate().flat_map(|(u, d)| std::iter::repeat_n(u, *d)).collect();
+        stubs.shuffle(&mut rng);
+        let pairs: HashSet<(usize, usize)> = stubs.chunks(2)
+            .filter(|stub| stub[0] != stub[1])
+            .map(|stub| pair(stub[0], stub[1]))
+            .collect();
+        graph_from_pairs(degrees.len(), &pairs)
+    }
+}
+
+// Influence of the users in a forward graph, the ground truth the fuzzy values are scored against.
+// Node weights are the user ids, and k-shell, degree and betweenness read the graph as undirected.
+pub mod influence {
+    extern crate petgraph;
+
+    use petgraph::{graph::Graph, EdgeType};
+    use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeSet, HashMap, VecDeque};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Influence {
+        #[serde(rename = "k-shell")]
+        KShell,
+        Degree,
+        Betweenness,
+        PageRank,
+    }
+
+    // (id, is_true_positive, fuzzy value, influence level)
+    pub type InfRecord = (usize, usize, f64, usize);
+
+    impl Influence {
+        pub fn name(&self) -> &'static str {
+            match self {
+                Influence::KShell => "k-shell",
+                Influence::Degree => "degree",
+                Influence::Betweenness => "betweenness",
+                Influence::PageRank => "pagerank",
+            }
+        }
+
+        pub fn scores<E, Ty: EdgeType>(&self, graph: &Graph<usize, E, Ty>) -> HashMap<usize, f64> {
+            match self {
+                Influence::KShell => k_shell(graph).into_iter().map(|(n, k)| (n, k as f64)).collect(),
+                Influence::Degree => degree(graph).into_iter().map(|(n, d)| (n, d as f64)).collect(),
+                Influence::Betweenness => betweenness(graph),
+                Influence::PageRank => pagerank(graph, &0.85),
+            }
+        }
+
+        // k-shell and degree as they are, betweenness and pagerank as deciles 1..=10 of the score
+        pub fn levels<E, Ty: EdgeType>(&self, graph: &Graph<usize, E, Ty>) -> HashMap<usize, usize> {
+            match self {
+                Influence::KShell => k_shell(graph),
+                Influence::Degree => degree(graph),
+                Influence::Betweenness | Influence::PageRank => {
+                    let mut scores: Vec<(usize, f64)> = self.scores(graph).into_iter().collect();
+                    scores.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+                    let n = scores.len();
+                    let mut levels = HashMap::<usize, usize>::new();
+                    let mut level = 1;
+                    for (rank, (node, score)) in scores.iter().enumerate() {
+                        // ties share the level of the first of them
+                        if (rank == 0) || (*score != scores[rank - 1].1) {
+                            level = 1 + rank * 10 / n;
+                        }
+                        levels.insert(*node, level);
+                    }
+                    levels
+                },
+            }
+        }
+
+        // a record for every node with a fuzzy value, the level is 0 for nodes outside fwd_graph
+        pub fn records<E, Ty: EdgeType>(&self, node_val: &HashMap<usize, f64>, fwd_graph: &Graph<usize, E, Ty>) -> Vec<InfRecord> {
+            let levels = self.levels(fwd_graph);
+            let mut records: Vec<InfRecord> = node_val.iter().map(|(node, val)| match levels.get(node) {
+                Some(level) => (*node, 1, *val, *level),
+                None => (*node, 0, *val, 0),
+            }).collect();
+            records.sort_by_key(|r| r.0);
+            records
+        }
+    }
+
+    // distinct nbrs in either direction, without self loops, by node index
+    fn undirected_nbrs<E, Ty: EdgeType>(graph: &Graph<usize, E, Ty>) -> Vec<Vec<usize>> {
+        let mut nbrs: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); graph.node_count()];
+        for (u, v) in graph.edge_indices().map(|e| graph.edge_endpoints(e).unwrap()) {
+            if u != v {
+                nbrs[u.index()].insert(v.index());
+                nbrs[v.index()].insert(u.index());
+            }
+        }
+        nbrs.into_iter().map(|set| set.into_iter().collect()).collect()
+    }
+
+    fn by_weight<E, Ty: EdgeType, T>(graph: &Graph<usize, E, Ty>, values: Vec<T>) -> HashMap<usize, T> {
+        graph.node_indices().zip(values).map(|(n, value)| (graph[n], value)).collect()
+    }
+
+    pub fn degree<E, Ty: EdgeType>(graph: &Graph<usize, E, Ty>) -> HashMap<usize, usize> {
+        by_weight(graph, undirected_nbrs(graph).iter().map(|nbrs| nbrs.len()).collect())
+    }
+
+    // the core number of every node, which is the k-shell it is in, by peeling nodes in degree order (Batagelj–Zaversnik)
+    pub fn k_shell<E, Ty: EdgeType>(graph: &Graph<usize, E, Ty>) -> HashMap<usize, usize> {
+        let nbrs = undirected_nbrs(graph);
+        let n = nbrs.len();
+        let mut deg: Vec<usize> = nbrs.iter().map(|v| v.len()).collect();
+        let max_deg = deg.iter().copied().max().unwrap_or(0);
+        // nodes sorted by degree in vert, with bin[d] the position of the first node of degree d
+        let mut bin: Vec<usize> = vec![0; max_deg + 1];
+        deg.iter().for_each(|d| bin[*d] += 1);
+        let mut start = 0;
+        for count in bin.iter_mut() {
+            (*count, start) = (start, start + *count);
+        }
+        let mut pos: Vec<usize> = vec![0; n];
+        let mut vert: Vec<usize> = vec![0; n];
+        for v in 0..n {
+            pos[v] = bin[deg[v]];
+            vert[pos[v]] = v;
+            bin[deg[v]] += 1;
+        }
+        for d in (1..=max_deg).rev() {
+            bin[d] = bin[d - 1];
+        }
+        bin[0] = 0;
+        for i in 0..n {
+            let v = vert[i];
+            for u in &nbrs[v] {
+                if deg[*u] > deg[v] {
+                    // move u to the front of its bin, then into the bin below
+                    let (du, pu) = (deg[*u], pos[*u]);
+                    let (pw, w) = (bin[du], vert[bin[du]]);
+                    if *u != w {
+                        (pos[*u], pos[w]) = (pw, pu);
+                        (vert[pu], vert[pw]) = (w, *u);
+                    }
+                    bin[du] += 1;
+                    deg[*u] -= 1;
+                }
+            }
+        }
+        by_weight(graph, deg)
+    }
+
+    // shortest path betweenness (Brandes), normalized by (n-1)(n-2) as networkx does
+    pub fn betweenness<E, Ty: EdgeType>(graph: &Graph<usize, E, Ty>) -> HashMap<usize, f64> {
+        let nbrs = undirected_nbrs(graph);
+        let n = nbrs.len();
+        let mut centrality: Vec<f64> = vec![0.0; n];
+        for s in 0..n {
+            let mut stack = Vec::<usize>::new();
+            let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut sigma: Vec<f64> = vec![0.0; n];
+            let mut dist: Vec<Option<usize>> = vec![None; n];
+            (sigma[s], dist[s]) = (1.0, Some(0));
+            let mut queue = VecDeque::from([s]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                let dv = dist[v].unwrap();
+                for w in &nbrs[v] {
+                    if dist[*w].is_none() {
+                        dist[*w] = Some(dv + 1);
+                        queue.push_back(*w);
+                    }
+                    if dist[*w] == Some(dv + 1) {
+                        sigma[*w] += sigma[v];
+                        preds[*w].push(v);
+                    }
+                }
+            }
+            let mut delta: Vec<f64> = vec![0.0; n];
+            while let Some(w) = stack.pop() {
+                for v in &preds[w] {
+                    delta[*v] += sigma[*v] / sigma[w] * (1.0 + delta[w]);
+                }
+                (w != s).then(|| centrality[w] += delta[w]);
+            }
+        }
+        let scale = match n > 2 {
+            true => 1.0 / (((n - 1) * (n - 2)) as f64),
+            false => 0.5,
+        };
+        by_weight(graph, centrality.into_iter().map(|c| c * scale).collect())
+    }
+
+    // power iteration over the graph's own directions, dangling nodes spread their rank to every node
+    pub fn pagerank<E, Ty: EdgeType>(graph: &Graph<usize, E, Ty>, damping: &f64) -> HashMap<usize, f64> {
+        let n = graph.node_count();
+        if n == 0 {
+            return HashMap::new();
+        }
+        let out: Vec<Vec<usize>> = graph.node_indices().map(|v| {
+            let set: BTreeSet<usize> = graph.neighbors(v).map(|w| w.index()).collect();
+            set.into_iter().collect()
+        }).collect();
+        let mut rank: Vec<f64> = vec![1.0 / n as f64; n];
+        for _i in 0..100 {
+            let dangling: f64 = (0..n).filter(|v| out[*v].is_empty()).map(|v| rank[v]).sum();
+            let mut next: Vec<f64> = vec![(damping * dangling + 1.0 - damping) / n as f64; n];
+            for v in 0..n {
+                out[v].iter().for_each(|w| next[*w] += damping * rank[v] / out[v].len() as f64);
+            }
+            let err: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+            rank = next;
+            if err < n as f64 * 1e-6 {
+                break;
+            }
+        }
+        by_weight(graph, rank)
+    }
+}
+
+// Users that do not run the honest client, and what that does to the tag chains the tracing can follow.
+pub mod adversary {
+    extern crate petgraph;
+
+    use petgraph::graph::NodeIndex;
+    use petgraph::prelude::UnGraph;
+    use rand::seq::index;
+    use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+    use super::utils::SimRng;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum Behaviour {
+        // forwards over another channel, so its receivers hold the message without a tag from it
+        OffPlatform,
+        // re-types the message, so its forwards start a fresh chain, as `send_packet` with a zero prev_key
+        Retype,
+        // reports with a forged tag key, which links to no forward at all
+        FalseReport,
+        // hands the message to this many fresh accounts of its own, which forward it on fresh chains
+        Sybil(usize),
+    }
+
+    // `fraction` of the users, drawn uniformly, behave the same way
+    #[derive(Clone, Copy, Debug)]
+    pub struct Adversaries {
+        pub behaviour: Behaviour,
+        pub fraction: f32,
+    }
+
+    impl Adversaries {
+        pub fn assign(&self, sys_graph: &UnGraph::<usize, ()>, rng: &mut SimRng) -> HashMap<usize, Behaviour> {
+            let n = sys_graph.node_count();
+            let count = ((n as f32) * self.fraction).round() as usize;
+            index::sample(rng, n, count.min(n)).into_iter().map(|u| (u, self.behaviour)).collect()
+        }
+    }
+
+    // a spread as it happened, with the forwards grouped by the tag chain they are on
+    #[derive(Clone, Debug)]
+    pub struct AdversarialSpread {
+        // the system graph, with the sybil accounts appended
+        pub sys_graph: UnGraph::<usize, ()>,
+        // every forward, on the platform or not
+        pub edges: Vec<(usize,usize)>,
+        pub node_src: HashMap<usize,usize>,
+        // the chain of every tagged forward, named by the user that started it
+        pub chain: HashMap<(usize,usize), usize>,
+        // users whose reports carry a forged tag key
+        pub forged: HashSet<usize>,
+    }
+
+    // `edges` in the order they were forwarded, as every spread model returns them
+    pub fn apply(sys_graph: &UnGraph::<usize, ()>, edges: &[(usize,usize)], node_src: &HashMap<usize,usize>, adversaries: &HashMap<usize, Behaviour>) -> AdversarialSpread {
+        let mut graph = sys_graph.clone();
+        let mut all_edges = Vec::<(usize,usize)>::new();
+        let mut src: HashMap<usize,usize> = node_src.iter().filter(|(_, snd)| **snd == usize::MAX).map(|(n, snd)| (*n, *snd)).collect();
+        let mut chain = HashMap::<(usize,usize), usize>::new();
+        // the chain each user forwards on, None when it got the message off the platform
+        let mut fwd_chain: HashMap<usize, Option<usize>> = src.keys().map(|n| (*n, Some(*n))).collect();
+        let mut sybils = HashMap::<usize, Vec<usize>>::new();
+
+        for (snd, rcv) in edges {
+            let (snd, tagged) = match adversaries.get(snd) {
+                Some(Behaviour::OffPlatform) => (*snd, false),
+                Some(Behaviour::Sybil(count)) if *count > 0 => {
+                    let accounts = sybils.entry(*snd).or_insert_with(|| (0..*count).map(|_| {
+                        let sybil = graph.add_node(graph.node_count()).index();
+                        graph.add_edge(NodeIndex::new(*snd), NodeIndex::new(sybil), ());
+                        all_edges.push((*snd, sybil));
+                        src.insert(sybil, *snd);
+                        fwd_chain.insert(sybil, Some(sybil));
+                        sybil
+                    }).collect());
+                    let sybil = accounts[*rcv % accounts.len()];
+                    graph.update_edge(NodeIndex::new(sybil), NodeIndex::new(*rcv), ());
+                    (sybil, true)
+                },
+                _ => (*snd, true),
+            };
+            let on_chain = match adversaries.get(&snd) {
+                Some(Behaviour::Retype) => Some(snd),
+                _ => *fwd_chain.get(&snd).unwrap_or(&None),
+            };
+            all_edges.push((snd, *rcv));
+            // a user that got the message off the platform pastes it, which starts a chain
+            tagged.then(|| chain.insert((snd, *rcv), on_chain.unwrap_or(snd)));
+            if let Entry::Vacant(entry) = src.entry(*rcv) {
+                entry.insert(snd);
+                fwd_chain.insert(*rcv, tagged.then(|| chain[&(snd, *rcv)]));
+            }
+        }
+        let forged = adversaries.iter().filter(|(_, b)| **b == Behaviour::FalseReport).map(|(u, _)| *u).collect();
+        AdversarialSpread { sys_graph: graph, edges: all_edges, node_src: src, chain, forged }
+    }
+
+    impl AdversarialSpread {
+        // the forwards a report from `reporter` links to, i.e. the chain it got the message on,
+        // with the first sender of every user on it; nothing for a forged report
+        pub fn traceable(&self, reporter: &usize) -> (Vec<(usize,usize)>, HashMap<usize,usize>) {
+            let on_chain = self.node_src.get(reporter).and_then(|snd| self.chain.get(&(*snd, *reporter)));
+            let (Some(on_chain), false) = (on_chain, self.forged.contains(reporter)) else {
+                return (Vec::new(), HashMap::new());
+            };
+            let edges: Vec<(usize,usize)> = self.edges.iter().filter(|e| self.chain.get(e) == Some(on_chain)).copied().collect();
+            let mut src = HashMap::from([(*on_chain, usize::MAX)]);
+            edges.iter().for_each(|(snd, rcv)| { src.entry(*rcv).or_insert(*snd); });
+            (edges, src)
+        }
+    }
+}
+
 pub mod fuzzy_traceback {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     use petgraph::{graph::{NodeIndex, Graph}, visit::IntoNodeReferences, Direction::{self, Incoming, Outgoing}};
     use petgraph::prelude::UnGraph;
     use probability::{distribution::Binomial, prelude::Discrete};
 
-    use super::{utils::{rand_state, vec_to_graph, hmap_to_graph}, sir::vec_edge_exists};
+    use super::{utils::{rand_state, vec_to_graph, hmap_to_graph, EdgeSet, SimRng}, sir::vec_edge_exists};
 
-    #[derive(Debug, Clone, PartialEq)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct TraceMd {
         node: usize,
         prev_node: usize,
     }
 
-    pub fn fuzz_bfs(full_graph: &UnGraph::<usize,()>, subgraph: &Graph::<usize,()>, full_sub_index_map: &HashMap<usize, NodeIndex>, start_node: &usize, fpr: &f32) -> (Graph::<usize, ()>, HashMap::<usize, NodeIndex>) {
+    pub fn fuzz_bfs(full_graph: &UnGraph::<usize,()>, subgraph: &Graph::<usize,()>, full_sub_index_map: &HashMap<usize, NodeIndex>, start_node: &usize, fpr: &f32, rng: &mut SimRng) -> (Graph::<usize, ()>, HashMap::<usize, NodeIndex>) {
         let mut curr_nodes = Vec::<NodeIndex>::new();
         let mut next_nodes = Vec::<NodeIndex>::new();
-        let mut searched_nodes = Vec::<NodeIndex>::new();
-        let mut fuzz_edge_list = Vec::<(usize,usize)>::new();
+        let mut searched_nodes = HashSet::<NodeIndex>::new();
+        let mut fuzz_edge_list = EdgeSet::new();
         curr_nodes.push(NodeIndex::from(*start_node as u32));
 
         while curr_nodes.len() != 0 {
@@ -288,25 +1228,38 @@ pub mod fuzzy_traceback {
                         let nbr_fwd_index = full_sub_index_map.get(&(nbr.index())).unwrap();
                         is_positive = subgraph.contains_edge(*node_fwd_index, *nbr_fwd_index) | subgraph.contains_edge(*nbr_fwd_index, *node_fwd_index);
                     }
-                    else { is_positive = rand_state(fpr); }
+                    else { is_positive = rand_state(fpr, rng); }
                     is_positive.then(|| {
                         vec_edge_exists(&false, &fuzz_edge_list, &nbr, &node).then(|| fuzz_edge_list.push((node.index(), nbr.index())));
                         (!searched_nodes.contains(&nbr)).then(|| next_nodes.push(nbr));
                     });
                 }
             }
-            searched_nodes.append(&mut curr_nodes);
+            searched_nodes.extend(curr_nodes.drain(..));
             curr_nodes.append(&mut next_nodes);
         }
-        vec_to_graph(&fuzz_edge_list)
+        vec_to_graph(fuzz_edge_list.as_vec())
+    }
+
+    // the (depth, previous node) of every traced edge
+    pub type TracedEdges = HashMap<(usize,usize),(usize,usize)>;
+
+    // the graphs and the rate every round of a fuzzy trace searches with
+    struct FuzzySearch<'a> {
+        full_graph: &'a UnGraph<usize, ()>,
+        id_map: &'a HashMap<usize, NodeIndex>,
+        subgraph: &'a Graph<usize, ()>,
+        fwd_srcs: &'a HashMap<usize, usize>,
+        fpr: &'a f32,
     }
 
     // divide the traced path into forward path and backward path
-    pub fn fuzzy_trace_ours(full_graph: &UnGraph::<usize,()>, subgraph: &Graph::<usize,()>, full_sub_index_map: &HashMap<usize, NodeIndex>, fwd_srcs: &HashMap<usize, usize>, start_node: &usize, fpr: &f32) -> (HashMap::<(usize,usize),(usize,usize)>, HashMap::<(usize,usize),(usize,usize)>, usize) {
+    pub fn fuzzy_trace_ours(full_graph: &UnGraph::<usize,()>, subgraph: &Graph::<usize,()>, full_sub_index_map: &HashMap<usize, NodeIndex>, fwd_srcs: &HashMap<usize, usize>, start_node: &usize, fpr: &f32, rng: &mut SimRng) -> (TracedEdges, TracedEdges, usize) {
+        let search = FuzzySearch { full_graph, id_map: full_sub_index_map, subgraph, fwd_srcs, fpr };
         let mut bwd_curr_tmd = Vec::<TraceMd>::new();
         let mut fwd_curr_tmd = Vec::<TraceMd>::new();
-        let mut bwd_srched_nodes = Vec::<TraceMd>::new();
-        let mut fwd_srched_nodes = Vec::<TraceMd>::new();
+        let mut bwd_srched_nodes = HashSet::<TraceMd>::new();
+        let mut fwd_srched_nodes = HashSet::<TraceMd>::new();
         let mut bwd_traced_edges = HashMap::<(usize,usize),(usize,usize)>::new();
         let mut fwd_traced_edges = HashMap::<(usize,usize),(usize,usize)>::new();
         let mut depth: usize = 0;
@@ -317,16 +1270,17 @@ pub mod fuzzy_traceback {
         while (bwd_curr_tmd.len() != 0) | (fwd_curr_tmd.len() != 0) {
             fwd_curr_tmd.append(&mut bwd_curr_tmd.clone());
             // backward search
-            one_round_search(&true, &mut bwd_curr_tmd, full_graph, full_sub_index_map, subgraph, fpr, depth, &mut bwd_traced_edges, &mut bwd_srched_nodes, fwd_srcs);
+            one_round_search(&search, &true, &mut bwd_curr_tmd, depth, &mut bwd_traced_edges, &mut bwd_srched_nodes, rng);
             // forward search
-            one_round_search(&false, &mut fwd_curr_tmd, full_graph, full_sub_index_map, subgraph, fpr, depth, &mut fwd_traced_edges, &mut fwd_srched_nodes, fwd_srcs);
+            one_round_search(&search, &false, &mut fwd_curr_tmd, depth, &mut fwd_traced_edges, &mut fwd_srched_nodes, rng);
             depth += 1;
         }
         
         (bwd_traced_edges, fwd_traced_edges, depth - 1)
     }
 
-    fn one_round_search(is_bwd: &bool, curr_tmd: &mut Vec<TraceMd>, full_graph: &UnGraph<usize, ()>, id_map: &HashMap<usize, NodeIndex>, subgraph: &Graph<usize, ()>, fpr: &f32, depth: usize, edge_list: &mut HashMap<(usize,usize), (usize,usize)>, searched_tmd: &mut Vec<TraceMd>, fwd_srcs: &HashMap<usize, usize>) {
+    fn one_round_search(search: &FuzzySearch, is_bwd: &bool, curr_tmd: &mut Vec<TraceMd>, depth: usize, edge_list: &mut TracedEdges, searched_tmd: &mut HashSet<TraceMd>, rng: &mut SimRng) {
+        let FuzzySearch { full_graph, id_map, subgraph, fwd_srcs, fpr } = search;
         let mut next_tmd = Vec::<TraceMd>::new();
 
         curr_tmd.iter().for_each(|tmd|{
@@ -349,7 +1303,7 @@ pub mod fuzzy_traceback {
                         false => subgraph.contains_edge(*snd_sub_id, *rcv_sub_id),
                     };
                 }
-                else { is_false_positive = rand_state(fpr); }
+                else { is_false_positive = rand_state(fpr, rng); }
                 
                 while is_true_positive | is_false_positive {
 
@@ -365,7 +1319,7 @@ pub mod fuzzy_traceback {
                 }
             });
         });
-        searched_tmd.append(curr_tmd);
+        searched_tmd.extend(curr_tmd.drain(..));
         curr_tmd.append(&mut next_tmd);
     }
 
@@ -646,14 +1600,14 @@ pub mod fuzzy_traceback {
         }
     }
 
-    pub fn any_leaf (fwd_graph: &Graph::<usize,()>) -> usize {
+    pub fn any_leaf (fwd_graph: &Graph::<usize,()>) -> Option<usize> {
         for n in fwd_graph.node_references() {
             if fwd_graph.neighbors_directed(n.0, Direction::Outgoing).count() == 0 {
                 let weight = fwd_graph.node_weight(NodeIndex::from(n.0.index() as u32)).unwrap();
-                return *weight
+                return Some(*weight)
             }
         }
-        return 1000000
+        None
     }
 
     pub fn degree_analysis (sub_graph: &Graph::<usize,()>, full_graph: &UnGraph::<usize,()>) -> f64 {
@@ -678,9 +1632,16 @@ mod tests {
     use crate::db;
     use crate::rwc_eval::rwc_eval;
 
-    use crate::simulation::{sir, fuzzy_traceback::{fuzz_bfs, self, degree_analysis, fuzzy_trace_ours, calc_fuz_val}, utils::{import_graph, graph_to_dot, fuz_val_to_graph, write_val_to_file, hmap_to_graph, vec_to_graph, gen_raw_data_file, graph_to_dot_for_draw, derive_graph_id_wt_map}};
+    use petgraph::{graph::NodeIndex, prelude::UnGraph};
+    use crate::simulation::spread::{SpreadModel, Sir, Sis, Seir, IndependentCascade, LinearThreshold, SeedSelection, origin_of};
+    use crate::simulation::temporal::{TemporalGraph, TemporalSir};
+    use crate::simulation::adversary::{self, Behaviour};
+    use crate::simulation::influence::{self, Influence};
+    use crate::simulation::generators;
+    use crate::simulation::{sir, fuzzy_traceback::{fuzz_bfs, self, degree_analysis, fuzzy_trace_ours, calc_fuz_val}, utils::{import_graph, graph_to_dot, fuz_val_to_graph, write_val_to_file, hmap_to_graph, vec_to_graph, gen_raw_data_file, gen_inf_csv_file, graph_to_dot_for_draw, derive_graph_id_wt_map, SimSeed}};
 
-    use super::utils::dedup_in_db_file;
+    use std::time::{Duration, Instant};
+    use super::utils::{dedup_in_db_file, dedup_vec_edges};
   
     #[test]
     fn test_remove_replicates() {
@@ -701,26 +1662,231 @@ mod tests {
         let (file_dir, st_node, round, s2i, i2r) = rwc_eval::select_dataset(&rwc_eval::Dataset::CollegeIM);
         let sys_graph = import_graph(file_dir);
         println!("{:?}, {:?}", sys_graph.node_count(), sys_graph.edge_count());
-        let (infected_edges, _) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone());
+        let mut rng = SimSeed::from_env().rng();
+        let (infected_edges, _) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone(), &mut rng);
         let (fwd_graph, _) = vec_to_graph(&infected_edges);
         println!("{:?}, {:?}", fwd_graph.node_count(), fwd_graph.edge_count());
 
         // graph_to_dot(&fwd_graph, "output/fwd_graph.dot".to_string());
     }
 
+    #[test]
+    fn test_spread_models() {
+        // a 6x6 grid
+        let edges: Vec<(u32,u32)> = (0..36u32).flat_map(|n| {
+            let right = (n % 6 < 5).then(|| (n, n + 1));
+            let down = (n < 30).then(|| (n, n + 6));
+            right.into_iter().chain(down)
+        }).collect();
+        let sys_graph = UnGraph::<usize, ()>::from_edges(&edges);
+        let models: Vec<Box<dyn SpreadModel>> = vec![
+            Box::new(Sir { s2i: 0.5, i2r: 0.3 }),
+            Box::new(Sis { s2i: 0.5, i2s: 0.3 }),
+            Box::new(Seir { s2e: 0.5, e2i: 0.5, i2r: 0.3 }),
+            Box::new(IndependentCascade { p: 0.5 }),
+            Box::new(LinearThreshold),
+        ];
+        let seed = SimSeed::new(7);
+        for model in models {
+            // the same seed gives the same spread
+            let (edges, node_src) = model.spread(&10, &14, &sys_graph, &mut seed.rng());
+            assert_eq!(model.spread(&10, &14, &sys_graph, &mut seed.rng()), (edges.clone(), node_src.clone()));
+            assert_eq!(node_src.get(&14), Some(&usize::MAX));
+            for (snd, rcv) in &edges {
+                assert!(sys_graph.contains_edge(NodeIndex::new(*snd), NodeIndex::new(*rcv)));
+                assert!(node_src.contains_key(snd) & node_src.contains_key(rcv));
+            }
+            node_src.iter().filter(|(_, src)| **src != usize::MAX).for_each(|(n, src)| assert!(edges.contains(&(*src, *n))));
+        }
+        let (edges, node_src) = IndependentCascade { p: 1.0 }.spread(&36, &0, &sys_graph, &mut seed.rng());
+        assert_eq!((edges.len(), node_src.len()), (35, 36));
+    }
+
+    #[test]
+    fn test_multi_seed_spread() {
+        // two 3x3 grids joined by 8-9
+        let edges: Vec<(u32,u32)> = (0..18u32).flat_map(|n| {
+            let right = (n % 3 < 2).then(|| (n, n + 1));
+            let down = (n % 9 < 6).then(|| (n, n + 3));
+            right.into_iter().chain(down)
+        }).chain([(8, 9)]).collect();
+        let sys_graph = UnGraph::<usize, ()>::from_edges(&edges);
+        let mut rng = SimSeed::new(7).rng();
+
+        assert_eq!(SeedSelection::Fixed(vec![9, 0, 9]).select(&sys_graph, &mut rng), vec![0, 9]);
+        assert_eq!(SeedSelection::HighestDegree(2).select(&sys_graph, &mut rng), vec![4, 13]);
+        assert_eq!(SeedSelection::Random(3).select(&sys_graph, &mut rng).len(), 3);
+        let cluster = SeedSelection::Clustered { count: 3, radius: 1 }.select(&sys_graph, &mut rng);
+        // all within one hop of some centre
+        assert_eq!(cluster.len(), 3);
+        assert!((0..18).any(|c| cluster.iter().all(|v| (*v == c) | sys_graph.contains_edge(NodeIndex::new(c), NodeIndex::new(*v)))));
+
+        // each seed reaches its own grid first
+        let (edges, node_src) = IndependentCascade { p: 1.0 }.spread_seeds(&2, &[0, 17], &sys_graph, &mut rng);
+        assert_eq!((node_src[&0], node_src[&17]), (usize::MAX, usize::MAX));
+        let origin = origin_of(&node_src);
+        assert!((0..9).filter(|n| origin.contains_key(n)).all(|n| origin[&n] == 0));
+        assert!((9..18).filter(|n| origin.contains_key(n)).all(|n| origin[&n] == 17));
+        let (sir_edges, sir_src) = Sir { s2i: 1.0, i2r: 0.0 }.spread_seeds(&10, &[0, 17], &sys_graph, &mut rng);
+        assert_eq!(sir_src.len(), 18);
+        assert_eq!(origin_of(&sir_src).values().filter(|seed| **seed == usize::MAX).count(), 0);
+
+        // tracing from a user reached by 17 without false positives finds 17 but not 0
+        let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&edges);
+        let (bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &11, &0.0, &mut rng);
+        let (memb_val, _) = calc_fuz_val(&0.01, &max_depth, &11, &bwd_traced_edges, &mut fwd_traced_edges, &sys_graph);
+        assert!(memb_val.contains_key(&17) & !memb_val.contains_key(&0));
+        let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&sir_edges);
+        let (bwd_traced_edges, fwd_traced_edges, _) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &sir_src, &11, &0.0, &mut rng);
+        assert!(bwd_traced_edges.keys().chain(fwd_traced_edges.keys()).all(|(u, v)| sys_graph.contains_edge(NodeIndex::new(*u), NodeIndex::new(*v))));
+    }
+
+    #[test]
+    fn test_adversary_chains() {
+        let edges = vec![(0, 1), (1, 2), (1, 3), (2, 4), (3, 5)];
+        let sys_graph = UnGraph::<usize, ()>::from_edges(edges.iter().map(|(u, v)| (*u as u32, *v as u32)));
+        let node_src = HashMap::from([(0, usize::MAX), (1, 0), (2, 1), (3, 1), (4, 2), (5, 3)]);
+        let apply = |behaviour: Behaviour, user: usize| adversary::apply(&sys_graph, &edges, &node_src, &HashMap::from([(user, behaviour)]));
+
+        let honest = adversary::apply(&sys_graph, &edges, &node_src, &HashMap::new());
+        assert_eq!(honest.traceable(&4), (edges.clone(), node_src.clone()));
+
+        // the re-typed forwards are one chain from 1
+        let (chain, src) = apply(Behaviour::Retype, 1).traceable(&4);
+        assert_eq!((chain, src[&1]), (vec![(1, 2), (1, 3), (2, 4), (3, 5)], usize::MAX));
+        assert_eq!(apply(Behaviour::Retype, 1).traceable(&1).0, vec![(0, 1)]);
+
+        // 2 and 3 get it off the platform and each pastes it
+        let spread = apply(Behaviour::OffPlatform, 1);
+        assert_eq!(spread.traceable(&4), (vec![(2, 4)], HashMap::from([(2, usize::MAX), (4, 2)])));
+        assert_eq!(spread.traceable(&2).0, vec![]);
+        assert_eq!(spread.edges, edges);
+
+        let spread = apply(Behaviour::FalseReport, 4);
+        assert_eq!((spread.traceable(&4).0, spread.traceable(&5).0), (vec![], edges.clone()));
+
+        // 1 hands the message to sybils 6 and 7, which forward it to 2 and 3
+        let spread = apply(Behaviour::Sybil(2), 1);
+        assert_eq!(spread.edges, vec![(0, 1), (1, 6), (1, 7), (6, 2), (7, 3), (2, 4), (3, 5)]);
+        assert_eq!((spread.sys_graph.node_count(), spread.node_src[&2], spread.node_src[&6]), (8, 6, 1));
+        assert!(spread.sys_graph.contains_edge(NodeIndex::new(6), NodeIndex::new(2)));
+        assert_eq!(spread.traceable(&4).0, vec![(6, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn test_influence() {
+        // a triangle with a tail 2-3-4 and a leaf 5 on 1, values checked against networkx
+        let (fwd_graph, _) = vec_to_graph(&vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (1, 5)]);
+        assert_eq!(influence::k_shell(&fwd_graph), HashMap::from([(0, 2), (1, 2), (2, 2), (3, 1), (4, 1), (5, 1)]));
+        assert_eq!(influence::degree(&fwd_graph), HashMap::from([(0, 2), (1, 3), (2, 3), (3, 2), (4, 1), (5, 1)]));
+        let btw = influence::betweenness(&fwd_graph);
+        assert!(((btw[&1] - 0.4).abs() < 1e-9) & ((btw[&2] - 0.6).abs() < 1e-9) & (btw[&0] == 0.0));
+        let pr = influence::pagerank(&fwd_graph, &0.85);
+        assert!(((pr[&1] - 0.197322).abs() < 1e-6) & ((pr[&2] - 0.159398).abs() < 1e-6) & ((pr.values().sum::<f64>() - 1.0).abs() < 1e-9));
+        assert_eq!(Influence::PageRank.levels(&fwd_graph), HashMap::from([(0, 1), (3, 1), (2, 4), (5, 4), (1, 7), (4, 7)]));
+
+        let node_val = HashMap::from([(9, 50.0), (1, 100.0)]);
+        assert_eq!(Influence::KShell.records(&node_val, &fwd_graph), vec![(1, 1, 100.0, 2), (9, 0, 50.0, 0)]);
+    }
+
+    #[test]
+    fn test_temporal_spread() {
+        // 1-2 only talk before 1 has the message, so it has to reach 2 through 3, and 1-5 talk in the same second 1 gets it
+        let graph = TemporalGraph::from_contacts(vec![(1, 3, 20), (0, 1, 10), (1, 5, 10), (3, 2, 30), (1, 2, 5), (3, 4, 100)]);
+        let mut rng = SimSeed::new(7).rng();
+        let (edges, node_src, recv_time) = TemporalSir { graph: &graph, s2i: 1.0, period: 50 }.spread_from(&10, &0, &10, &mut rng);
+        assert_eq!(edges, vec![(0, 1), (1, 3), (3, 2)]);
+        assert_eq!((node_src[&2], recv_time[&2]), (3, 30));
+        assert!(!node_src.contains_key(&4) & !node_src.contains_key(&5));
+        let (edges, _, _) = TemporalSir { graph: &graph, s2i: 1.0, period: 5 }.spread_from(&10, &0, &10, &mut rng);
+        assert_eq!(edges, vec![(0, 1)]);
+
+        // fuzzy traceback on the temporal forwarding tree over the static graph
+        let sys_graph = graph.static_graph();
+        let model = TemporalSir { graph: &graph, s2i: 1.0, period: 50 };
+        let (infected_edges, node_src) = model.spread(&10, &0, &sys_graph, &mut rng);
+        let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&infected_edges);
+        let (bwd_traced_edges, _, _) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &2, &0.0, &mut rng);
+        let mut bwd_edges: Vec<(usize,usize)> = bwd_traced_edges.into_keys().collect();
+        bwd_edges.sort();
+        assert_eq!(bwd_edges, vec![(1, 0), (2, 3), (3, 1)]);
+    }
+
+    #[test]
+    fn test_generators() {
+        let seed = SimSeed::new(7);
+        let edges = |g: &UnGraph<usize, ()>| g.edge_indices().map(|e| g.edge_endpoints(e).unwrap()).collect::<Vec<_>>();
+        let no_loops = |g: &UnGraph<usize, ()>| g.edge_indices().all(|e| { let (u, v) = g.edge_endpoints(e).unwrap(); u != v });
+
+        let er = generators::erdos_renyi(&200, &0.05, &seed);
+        assert_eq!(edges(&er), edges(&generators::erdos_renyi(&200, &0.05, &seed)));
+        assert_ne!(edges(&er), edges(&generators::erdos_renyi(&200, &0.05, &seed.trial(1))));
+        assert!((er.node_count() == 200) & (700..1300).contains(&er.edge_count()));
+
+        let ba = generators::barabasi_albert(&200, &3, &seed);
+        assert_eq!((ba.node_count(), ba.edge_count()), (200, 6 + 196 * 3));
+        assert!(no_loops(&ba));
+
+        let ws = generators::watts_strogatz(&100, &4, &0.1, &seed);
+        assert_eq!((ws.node_count(), ws.edge_count()), (100, 200));
+        assert!(no_loops(&ws));
+
+        let sbm = generators::stochastic_block(&[50, 50], &[vec![0.3, 0.01], vec![0.01, 0.3]], &seed);
+        let within = sbm.edge_indices().filter(|e| { let (u, v) = sbm.edge_endpoints(*e).unwrap(); (u.index() < 50) == (v.index() < 50) }).count();
+        assert!(within > 10 * (sbm.edge_count() - within));
+
+        let cm = generators::configuration_model(&vec![3; 100], &seed);
+        assert!((cm.node_count() == 100) & no_loops(&cm) & cm.node_indices().all(|n| cm.neighbors(n).count() <= 3));
+    }
+
+    // one run of each stage on a million-node preferential attachment graph, best with --release,
+    // a few seconds there and well within the budget below
+    #[test]
+    #[ignore = "generates a million-node graph, run with --release -- --ignored test_scale_1m"]
+    fn test_scale_1m() {
+        let budget = Duration::from_secs(if cfg!(debug_assertions) { 300 } else { 30 });
+        let t_total = Instant::now();
+        let t_start = Instant::now();
+        let sys_graph = generators::barabasi_albert(&1_000_000, &3, &SimSeed::new(1));
+        println!("Generate: node {}, edge {}, {:?}", sys_graph.node_count(), sys_graph.edge_count(), t_start.elapsed());
+
+        let edges: Vec<(usize,usize)> = sys_graph.edge_indices()
+            .map(|e| sys_graph.edge_endpoints(e).unwrap())
+            .flat_map(|(u, v)| [(u.index(), v.index()), (v.index(), u.index())])
+            .collect();
+        let t_start = Instant::now();
+        let (graph, _) = vec_to_graph(&dedup_vec_edges(&edges));
+        println!("Dedup and build: edge {}, {:?}", graph.edge_count(), t_start.elapsed());
+
+        let mut rng = SimSeed::new(2).rng();
+        let t_start = Instant::now();
+        let (infected_edges, node_src) = sir::sir_spread(&20, &0, &0.05, &0.6, &sys_graph, &mut rng);
+        let (fwd_graph, fwd_to_sys_id_map) = vec_to_graph(&infected_edges);
+        println!("SIR spread: node {}, edge {}, {:?}", fwd_graph.node_count(), fwd_graph.edge_count(), t_start.elapsed());
+
+        let start_node = fuzzy_traceback::any_leaf(&fwd_graph).unwrap();
+        let t_start = Instant::now();
+        let (bwd_traced_edges, fwd_traced_edges, max_depth) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &0.0001, &mut rng);
+        println!("Fuzzy traceback: edge {}, depth {}, {:?}", bwd_traced_edges.len() + fwd_traced_edges.len(), max_depth, t_start.elapsed());
+        assert_eq!(graph.edge_count(), sys_graph.edge_count());
+        assert!(t_total.elapsed() < budget, "took {:?}, over the budget of {:?}", t_total.elapsed(), budget);
+    }
+
     #[test]
     fn test_fuzz_bfs() {
         let (file_dir, st_node, round, s2i, i2r) = rwc_eval::select_dataset(&rwc_eval::Dataset::CollegeIM);
         let sys_graph = import_graph(file_dir);
-        let (infected_edges, _) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone());
+        let seed = SimSeed::from_env();
+        let mut rng = seed.rng();
+        let (infected_edges, _) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone(), &mut rng);
         let (fwd_graph, sys_fwd_map) = vec_to_graph(&infected_edges);
         println!("Forward Graph: node {:?}, edge {:?}, mean degree: {:?}", fwd_graph.node_count(), fwd_graph.edge_count(), degree_analysis(&fwd_graph, &sys_graph));
-        graph_to_dot(&fwd_graph, "./output/fwd_graph.dot".to_string());
+        graph_to_dot(&fwd_graph, "./output/fwd_graph.dot".to_string(), &seed);
         // start from a leaf node
-        let start_node = fuzzy_traceback::any_leaf(&fwd_graph);
-        let (fuzz_graph, _) = fuzz_bfs(&sys_graph, &fwd_graph, &sys_fwd_map, &start_node, &0.01);
+        let start_node = fuzzy_traceback::any_leaf(&fwd_graph).unwrap();
+        let (fuzz_graph, _) = fuzz_bfs(&sys_graph, &fwd_graph, &sys_fwd_map, &start_node, &0.01, &mut rng);
         println!("Fuzzy Graph: node {:?}, edge {:?}, mean degree: {:?}", fuzz_graph.node_count(), fuzz_graph.edge_count(), degree_analysis(&fuzz_graph, &sys_graph));
-        graph_to_dot(&fuzz_graph, "./output/fuzz_graph.dot".to_string());
+        graph_to_dot(&fuzz_graph, "./output/fuzz_graph.dot".to_string(), &seed);
     }
 
     #[test]
@@ -728,11 +1894,13 @@ mod tests {
         let (file_dir, st_node, round, s2i, i2r) = rwc_eval::select_dataset(&rwc_eval::Dataset::CollegeIM);
         let sys_graph = import_graph(file_dir);
         let trace_fpr: f32 = 0.01;
+        let seed = SimSeed::from_env();
+        let mut rng = seed.rng();
 
         loop {
             db_clear();
             // 1.Generate a forward graph
-            let (infected_edges, node_src) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone());
+            let (infected_edges, node_src) = sir::sir_spread(&round, &st_node, &s2i, &i2r, &sys_graph.clone(), &mut rng);
             if infected_edges.len() < 200 {
                 continue;
             }
@@ -740,11 +1908,11 @@ mod tests {
 
             println!("Forward Graph: node {:?}, edge {:?}, mean degree: {:?}", fwd_graph.node_count(), fwd_graph.edge_count(), degree_analysis(&fwd_graph, &sys_graph));
             // graph_to_dot(&fwd_graph, "./output/fwd_graph.dot".to_string());
-            graph_to_dot(&fwd_graph, "python/graphs/graph_real.dot".to_string());
+            graph_to_dot(&fwd_graph, "python/graphs/graph_real.dot".to_string(), &seed);
 
             // 2. Run fuzzy traceback to generate the fuzzy forward graph
-            let start_node = fuzzy_traceback::any_leaf(&fwd_graph);
-            let (mut bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &trace_fpr);
+            let start_node = fuzzy_traceback::any_leaf(&fwd_graph).unwrap();
+            let (mut bwd_traced_edges, mut fwd_traced_edges, max_depth) = fuzzy_trace_ours(&sys_graph, &fwd_graph, &fwd_to_sys_id_map, &node_src, &start_node, &trace_fpr, &mut rng);
 
             // 3. Compute fuzzy values of nodes in fuzzy graph by the membership function
             let (mut memb_val, edge_fpr) = calc_fuz_val(&(trace_fpr as f64), &max_depth, &start_node, &mut bwd_traced_edges, &mut fwd_traced_edges, &sys_graph);
@@ -761,22 +1929,26 @@ mod tests {
             println!("Full fuzzy Graph: node {:?}, edge {:?}", full_fuz_graph.node_count(), full_fuz_graph.edge_count());
 
             // write graph to file for k-shell comparison
-            graph_to_dot(&full_fuz_graph, "python/graphs/graph_fuzzy.dot".to_string());
+            graph_to_dot(&full_fuz_graph, "python/graphs/graph_fuzzy.dot".to_string(), &seed);
             let sys_to_fuzz_id_map = derive_graph_id_wt_map(&full_fuz_graph);
-            write_val_to_file(&sys_to_fuzz_id_map, "python/inputs/id_map_fuzz.txt".to_string());
+            write_val_to_file(&sys_to_fuzz_id_map, "python/inputs/id_map_fuzz.txt".to_string(), &seed);
 
             let fuzzy_graph = fuz_val_to_graph(&full_fuz_graph, &memb_val, &edge_fpr);
-            graph_to_dot(&fuzzy_graph, "./output/graph_fuzzy.dot".to_string());
+            graph_to_dot(&fuzzy_graph, "./output/graph_fuzzy.dot".to_string(), &seed);
 
             // 3-2. generate raw data file, where one line a tuple (node_id, is_true_positive, fuzzy_val)
             memb_val.insert(start_node, 0.85);
-            gen_raw_data_file(&memb_val, &fwd_to_sys_id_map, "python/inputs/data_fuzzy_value.txt".to_string());
+            gen_raw_data_file(&memb_val, &fwd_to_sys_id_map, "python/inputs/data_fuzzy_value.txt".to_string(), &seed);
+
+            // 3-3. the records of the analysis, with the k-shell of every node in the forward graph
+            let metric = Influence::KShell;
+            gen_inf_csv_file(&metric.records(&memb_val, &fwd_graph), &metric, "python/inputs/fuz_val_and_inf.csv".to_string(), &seed);
 
             let sys_to_fwd_id_map: HashMap<usize,usize> = fwd_to_sys_id_map.iter().map(|(k,v)|
                 (v.index(), *k)).collect();
-            write_val_to_file(&sys_to_fwd_id_map, "python/inputs/id_map_fwd.txt".to_string());
+            write_val_to_file(&sys_to_fwd_id_map, "python/inputs/id_map_fwd.txt".to_string(), &seed);
 
-            graph_to_dot_for_draw(&infected_edges, &fwd_traced_edges, &sys_graph, & "./output/full_graph_for_paper.dot".to_string());
+            graph_to_dot_for_draw(&infected_edges, &fwd_traced_edges, &sys_graph, & "./output/full_graph_for_paper.dot".to_string(), &seed);
             
             break;
         }
@@ -12,6 +12,7 @@ pub mod utils{
     use base64::decode;
     use sha3::{Digest, digest::{Update, ExtendableOutput, XofReader}, Sha3_256, Shake128};
     use tiny_keccak::{Kmac, Hasher};
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     // input abitray string, output 128bit hash
     pub fn hash(x: &String) -> [u8; 16] {
@@ -42,6 +43,11 @@ pub mod utils{
         y
     }
 
+    // unix time in seconds
+    pub fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
     // fn prf(k: &[u8; 16], x: &[u8]) -> [u8; 16] {
     //     let mut y: [u8; 16] = Default::default();
     //     y.copy_from_slice(&Sha3_256::digest(&[k, x].concat()).as_slice()[0..16]);
@@ -97,6 +103,24 @@ pub mod utils{
     }
 
     pub fn decryption(k: &[u8; 16], ciphertext: &[u8; 48]) -> [u8; 32] {
+        try_decryption(k, ciphertext).unwrap()
+    }
+
+    // for plaintexts of any length, where the caller never reuses k
+    pub fn encryption_bytes(k: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+        let hash_k: [u8; 32] = Sha3_256::digest(k).into();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&hash_k));
+        cipher.encrypt(Nonce::from_slice(b"unique nonce"), plaintext).unwrap()
+    }
+
+    pub fn try_decryption_bytes(k: &[u8; 16], ciphertext: &[u8]) -> Option<Vec<u8>> {
+        let hash_k: [u8; 32] = Sha3_256::digest(k).into();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&hash_k));
+        cipher.decrypt(Nonce::from_slice(b"unique nonce"), ciphertext).ok()
+    }
+
+    // None if the ciphertext does not authenticate under k
+    pub fn try_decryption(k: &[u8; 16], ciphertext: &[u8; 48]) -> Option<[u8; 32]> {
         let mut p: [u8; 32] = Default::default();
         // hash k to 32 bytes
         let mut hash_k: [u8; 32] = Default::default();
@@ -105,10 +129,10 @@ pub mod utils{
         let key = Key::<Aes256Gcm>::from_slice(&hash_k);
         let nonce = Nonce::from_slice(b"unique nonce"); // 96-bits; unique per message
         let cipher = Aes256Gcm::new(key);
-        let plaintext = cipher.decrypt(nonce, ciphertext.as_ref()).unwrap();
+        let plaintext = cipher.decrypt(nonce, ciphertext.as_ref()).ok()?;
         p.copy_from_slice(&plaintext);
-        p
-    } 
+        Some(p)
+    }
 }
 
 pub mod algos{
@@ -116,16 +140,23 @@ pub mod algos{
     extern crate lazy_static;
 
     use base64::encode;
+    use curve25519_dalek::Scalar;
     use crate::tool::utils::{hash, crprf, encipher, decipher};
     use crate::db::db_tag;
+    use crate::message::messaging::Uid;
 
     use super::utils::hash_array_32;
 
-    pub fn tk_gen(sik: &[u8; 16], rid: &u32) -> [u8; 16] {
+    pub fn tk_gen(sik: &[u8; 16], rid: &Uid) -> [u8; 16] {
         // convert sik and rid to string 
         hash(&(encode(sik) + &rid.to_string()))
     }
 
+    // group_tk_gen: tracing key of a sender in a group, derived from the group's identity key
+    pub fn group_tk_gen(gik: &[u8; 16], sid: &Uid) -> [u8; 16] {
+        hash(&(encode(gik) + "grp" + &sid.to_string()))
+    }
+
     // new_key_gen: generate a ramdom key
     pub fn new_key_gen(tk: &[u8; 16]) -> [u8; 16] {
         let key = rand::random::<[u8; 16]>();
@@ -151,6 +182,51 @@ pub mod algos{
         crprf(tag_key, &hash_msg)
     }
 
+    // hop counter: the proof of hop n is H^n(seed) and the anchor is H^MAX_HOP(seed),
+    // so anyone can move a counter forward but moving it back needs a hash preimage
+    pub const MAX_HOP: u32 = 64;
+
+    pub fn hop_next(proof: &[u8; 16]) -> [u8; 16] {
+        hash_array_32(proof)[..16].try_into().unwrap()
+    }
+
+    // returns (proof of hop 0, anchor)
+    pub fn hop_counter_gen() -> ([u8; 16], [u8; 16]) {
+        let seed = rand::random::<[u8; 16]>();
+        let anchor = (0..MAX_HOP).fold(seed, |proof, _| hop_next(&proof));
+        (seed, anchor)
+    }
+
+    pub fn hop_verify(hop: &u32, proof: &[u8; 16], anchor: &[u8; 16]) -> bool {
+        *hop <= MAX_HOP && (*hop..MAX_HOP).fold(*proof, |p, _| hop_next(&p)) == *anchor
+    }
+
+    pub fn random_scalar() -> Scalar {
+        let mut wide: [u8; 64] = [0; 64];
+        wide[..32].copy_from_slice(&rand::random::<[u8; 32]>());
+        wide[32..].copy_from_slice(&rand::random::<[u8; 32]>());
+        Scalar::from_bytes_mod_order_wide(&wide)
+    }
+
+    // shamir_split: k-of-n shares of a scalar mod the group order, share i is evaluated at x = i
+    pub fn shamir_split(secret: &Scalar, k: u8, n: u8) -> Vec<(u8, Scalar)> {
+        let coeffs: Vec<Scalar> = (1..k).map(|_| random_scalar()).collect();
+        (1..=n).map(|x| {
+            let at = Scalar::from(x);
+            // horner from the highest coefficient down to the secret
+            (x, coeffs.iter().rev().fold(Scalar::ZERO, |acc, c| acc * at + c) * at + secret)
+        }).collect()
+    }
+
+    // shamir_lagrange: the weights that interpolate the shares at xs to x = 0,
+    // so they also combine k multiples of one point into the multiple by the secret
+    pub fn shamir_lagrange(xs: &[u8]) -> Vec<Scalar> {
+        xs.iter().map(|xj| {
+            xs.iter().filter(|xm| *xm != xj)
+                .fold(Scalar::ONE, |acc, xm| acc * Scalar::from(*xm) * (Scalar::from(*xm) - Scalar::from(*xj)).invert())
+        }).collect()
+    }
+
     pub fn hk_gen(tk: &[u8; 16]) -> [u8; 16] {
         // hash the tk
         hash(&encode(tk))
@@ -195,6 +271,7 @@ mod tests {
     use crate::tool::utils::{encipher, decipher};
     use crate::tool::algos;
     use base64::encode;
+    use curve25519_dalek::Scalar;
     use test::Bencher;
 
     use super::utils::{self, encryption};
@@ -204,6 +281,19 @@ mod tests {
     //     let _ = env_logger::builder().is_test(true).try_init();
     // }
 
+    #[test]
+    fn shamir_threshold() {
+        let secret = algos::random_scalar();
+        let shares = algos::shamir_split(&secret, 3, 5);
+        let combine = |points: &[(u8, Scalar)]| {
+            let xs: Vec<u8> = points.iter().map(|(x, _)| *x).collect();
+            algos::shamir_lagrange(&xs).iter().zip(points).map(|(l, (_, share))| l * share).sum::<Scalar>()
+        };
+        assert_eq!(combine(&shares[..3]), secret);
+        assert_eq!(combine(&[shares[4], shares[1], shares[3]]), secret);
+        assert_ne!(combine(&shares[..2]), secret);
+    }
+
     // utils test
     #[test]
     fn enc_dec() {
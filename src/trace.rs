@@ -6,19 +6,35 @@ pub mod traceback {
     use std::collections::{HashSet, HashMap};
     use std::sync::{Arc, Mutex};
     use std::{thread, fmt};
-    use crate::message::messaging::{MsgReport, Edge};
+    use crate::message::messaging::{MsgReport, Edge, Uid};
     use crate::tool::algos;
-    use crate::db::{db_tag, db_nbr, db_ik};
+    use std::time::{Duration, Instant};
+    use serde::{Serialize, Deserialize};
+    use crate::db::{db_tag, db_nbr, db_grp, db_dev};
+    use crate::db::db_grp::MemberEvent;
+    use crate::trace::trace_result::{TraceResult, TraceEdge, TraceStats, SearchDir};
+    use crate::trace::trace_stream::TraceEvent;
+    use crate::trace::trace_scope::{TraceScope, ScopeGrant};
+    use crate::warrant::trace_warrant::{TraceWarrant, WarrantError};
+    use crate::audit::audit_log::AuditContext;
+    use crate::message::sealed_sender::{SealedBox, SealedData, TraceAuthority};
+    use crate::tool::utils::hash;
     use base64::encode;
-    #[derive(Clone, Debug)]
+    use x25519_dalek::PublicKey;
+    #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct TraceData {
-        pub uid: u32,
+        pub uid: Uid,
         pub key: [u8; 16],
+        // the group of the hop, if the message was sent to a group rather than to a user
+        pub gid: Option<Uid>,
     }
 
     impl TraceData {
-        pub fn new(id: u32, trace_key: [u8; 16]) -> TraceData {
-            TraceData { uid: id, key: trace_key }
+        pub fn new(id: Uid, trace_key: [u8; 16]) -> TraceData {
+            TraceData { uid: id, key: trace_key, gid: None }
+        }
+        pub fn via_group(id: Uid, trace_key: [u8; 16], gid: Uid) -> TraceData {
+            TraceData { uid: id, key: trace_key, gid: Some(gid) }
         }
         pub fn hash(&self) -> String {
             self.uid.to_string() + &encode(&self.key[..])
@@ -44,41 +60,129 @@ pub mod traceback {
         key_vec
     }
 
-    fn db_query_nbrs(vec_uid: &Vec<u32>) -> (Vec<Vec<u32>>, HashMap<u32,[u8;16]>){
+    fn hop_edge(sid: &Uid, rid: &Uid, gid: &Option<Uid>) -> Edge {
+        match gid {
+            Some(gid) => Edge::via_group(sid, gid, rid),
+            None => Edge::new(sid, rid),
+        }
+    }
+
+    // (nbrs of each user, identity keys of users and nbrs, membership history of the nbrs that are groups)
+    pub(crate) type NbrQuery = (Vec<Vec<Uid>>, HashMap<Uid,[u8;16]>, HashMap<Uid,Vec<MemberEvent>>);
+
+    // The identity keys are opened through the warrant, users and nbrs without a key are left out,
+    // so every nbr returned has its key in the map
+    fn db_query_nbrs(report: &MsgReport, vec_uid: &Vec<Uid>, warrant: &TraceWarrant) -> NbrQuery {
+        let (mut vec_vec_nbrs, map_id_ik, map_gid_hist) = db_query_nbrs_fused(report, vec_uid, warrant);
+        for (uid, nbrs) in vec_uid.iter().zip(vec_vec_nbrs.iter_mut()) {
+            match map_id_ik.contains_key(uid) {
+                true => nbrs.retain(|nbr| map_id_ik.contains_key(nbr)),
+                false => nbrs.clear(),
+            }
+        }
+        (vec_vec_nbrs, map_id_ik, map_gid_hist)
+    }
+
+    // one script call on db_nbr for the whole frontier, returning the sealed keys mirrored there
+    pub(crate) fn db_query_nbrs_fused(report: &MsgReport, vec_uid: &[Uid], warrant: &TraceWarrant) -> NbrQuery {
+        let mut vec_vec_nbrs = Vec::<Vec<Uid>>::new();
+        let mut map_id_sealed = HashMap::<Uid,SealedBox>::new();
+        let mut map_gid_hist = HashMap::<Uid,Vec<MemberEvent>>::new();
+        for (uid, (ik, nbrs)) in vec_uid.iter().zip(db_nbr::query_frontier(vec_uid).unwrap()) {
+            if let Some(ik) = ik {
+                map_id_sealed.insert(*uid, ik);
+            }
+            let mut vec_nbrs = Vec::<Uid>::new();
+            for (nbr, nbr_ik, history) in nbrs {
+                if let Some(nbr_ik) = nbr_ik {
+                    map_id_sealed.insert(nbr, nbr_ik);
+                }
+                if let Some(history) = history {
+                    map_gid_hist.insert(nbr, history);
+                }
+                vec_nbrs.push(nbr);
+            }
+            vec_vec_nbrs.push(vec_nbrs);
+        }
+        (vec_vec_nbrs, warrant.open_keys(report, &map_id_sealed), map_gid_hist)
+    }
+
+    // separate round trips to db_nbr, db_grp and db_ik
+    pub(crate) fn db_query_nbrs_split(report: &MsgReport, vec_uid: &Vec<Uid>, warrant: &TraceWarrant) -> NbrQuery {
         // query nbrs of users
         // let map_uid_nbr = db_nbr::query(vec_uid);
         let map_uid_nbr = db_nbr::query(vec_uid);
         // query ik of users
-        let values: Vec<Vec<u32>> = map_uid_nbr.clone().into_values().collect();
-        let mut vec_values: Vec<u32> = values.concat();
+        let values: Vec<Vec<Uid>> = map_uid_nbr.clone().into_values().collect();
+        let mut vec_values: Vec<Uid> = values.concat();
+        // query membership history of the nbrs that are groups
+        vec_values.sort();
+        vec_values.dedup();
+        let map_gid_hist = db_grp::histories(&vec_values);
         vec_values.append(&mut vec_uid.clone());
-        let map_id_ik = db_ik::query(&vec_values);
+        let map_id_ik = warrant.identity_keys(report, &vec_values);
 
-        let mut vec_vec_nbrs = Vec::<Vec<u32>>::new();
+        let mut vec_vec_nbrs = Vec::<Vec<Uid>>::new();
         for uid in vec_uid {
-            if map_uid_nbr.contains_key(uid) == true {
-                let nbrs = map_uid_nbr.get(uid).unwrap();
-                vec_vec_nbrs.push(nbrs.clone());
-            }
+            vec_vec_nbrs.push(map_uid_nbr.get(uid).cloned().unwrap_or_default());
+        }
+        (vec_vec_nbrs, map_id_ik, map_gid_hist)
+    }
+
+    // send time of each group tag, where a tag stored without one counts the current members
+    fn group_send_ts(tags: Vec<String>) -> HashMap<String, u64> {
+        let ts = db_tag::timestamps(&tags);
+        tags.into_iter().zip(ts).map(|(tag, ts)| (tag, ts.unwrap_or(u64::MAX))).collect()
+    }
+
+    // a user with several candidate predecessors, e.g. when a tag store false positive hides the real parent
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Ambiguity {
+        pub uid: Uid,
+        pub candidates: Vec<TraceData>,
+    }
+
+    // one db_tag membership query, by the number of tags and a digest of them
+    #[derive(Clone, Debug, Default)]
+    pub struct TagQuery {
+        pub lookups: usize,
+        pub digest: [u8; 16],
+    }
+
+    impl TagQuery {
+        fn of(tags: &[String]) -> Self {
+            TagQuery { lookups: tags.len(), digest: hash(&tags.concat()) }
         }
-        (vec_vec_nbrs, map_id_ik)
     }
 
-    pub fn par_backward_search(input_msg: &String, md: &TraceData) -> TraceData {
+    // returns every nbr whose tag matches, together with the recovered prev_key and the tag query made
+    fn backward_search(report: &MsgReport, md: &TraceData, warrant: &TraceWarrant) -> (Vec<TraceData>, TagQuery) {
         // let nbrs = redis_pack::query_users_receive(&md.uid);
-        let (vec_vec_nbrs, map_id_ik) = db_query_nbrs(&vec![md.uid]);
+        let input_msg = &report.payload;
+        let (vec_vec_nbrs, map_id_ik, map_gid_hist) = db_query_nbrs(report, &vec![md.uid], warrant);
         let vec_nbrs = vec_vec_nbrs.get(0).unwrap().to_owned();
-        let curr_id = Arc::new(md.uid);
+        // candidate senders as (sid, tk, gid), where a group expands to everyone who was ever a member
+        let mut candidates: Vec<(Uid, [u8; 16], Option<Uid>)> = Vec::new();
+        for nbr_id in &vec_nbrs {
+            let nbr_ik = map_id_ik.get(nbr_id).unwrap();
+            match map_gid_hist.get(nbr_id) {
+                Some(history) => db_grp::ever_members(history).iter()
+                    .filter(|member| **member != md.uid)
+                    .for_each(|member| candidates.push((*member, algos::group_tk_gen(nbr_ik, member), Some(*nbr_id)))),
+                None => candidates.push((*nbr_id, algos::tk_gen(nbr_ik, &md.uid), None)),
+            }
+        }
+        if candidates.is_empty() {
+            return (Vec::new(), TagQuery::default());
+        }
         let key = Arc::new(md.key);
         let message = Arc::new(input_msg.clone());
         let par_tags: Arc<Mutex<HashMap<usize, String>>> = Arc::new(Mutex::new(HashMap::new()));
         let mut thread_list = Vec::new();
-        for i in 0..vec_nbrs.len() {
+        for i in 0..candidates.len() {
             let lock_key = Arc::clone(&key);
             let lock_message = Arc::clone(&message);
-            let lock_curr_id = Arc::clone(&curr_id);
-            let nbr_id = vec_nbrs.get(i).unwrap();
-            let tk = algos::tk_gen(map_id_ik.get(&nbr_id).unwrap(), &lock_curr_id);
+            let tk = candidates.get(i).unwrap().1;
             let tags_hmap = par_tags.clone();
             
             let handle = thread::spawn(move || {
@@ -93,41 +197,61 @@ pub mod traceback {
         }
         let tags_hmap = Arc::try_unwrap(par_tags).unwrap().into_inner().unwrap();
         let mut bf_tags_vec: Vec<String> = hmap_to_vec_in_squence(&tags_hmap);
+        let query = TagQuery::of(&bf_tags_vec);
         let bf_result = db_tag::mexists(&mut bf_tags_vec);
-        let mut source: TraceData = TraceData::new(0, md.key);
+        let group_tags: Vec<String> = (0..bf_result.len())
+            .filter(|i| bf_result[*i] & candidates[*i].2.is_some())
+            .map(|i| bf_tags_vec[i].clone())
+            .collect();
+        let map_tag_ts = match group_tags.is_empty() {
+            true => HashMap::new(),
+            false => group_send_ts(group_tags),
+        };
+        let mut sources: Vec<TraceData> = Vec::new();
 
         for i in 0..(bf_result.len()) {
-            if *bf_result.get(i).unwrap() == true {
-                let nbr_id = vec_nbrs.get(i).unwrap();
-                let tk = algos::tk_gen(&map_id_ik.get(&nbr_id).unwrap(), &md.uid);
-                let prev_key = algos::prev_key(&md.key, &tk);
-                source = TraceData::new(*nbr_id, prev_key);
-                // Note that this break may cause bug when the first user is the source.
-                break;
+            if *bf_result.get(i).unwrap() {
+                let (snd_id, tk, gid) = candidates.get(i).unwrap();
+                // a group send only counts if both ends were members when it was sent
+                if let Some(gid) = gid {
+                    let members = db_grp::members_in(&map_gid_hist[gid], &map_tag_ts[&bf_tags_vec[i]]);
+                    if !members.contains(snd_id) | !members.contains(&md.uid) {
+                        continue;
+                    }
+                }
+                let prev_key = algos::prev_key(&md.key, tk);
+                sources.push(TraceData { uid: *snd_id, key: prev_key, gid: *gid });
             }
         }
-        source
+        (sources, query)
     }
 
-    pub fn par_forward_search(input_msg: &String, md: &Vec<TraceData>) -> Vec<Vec<TraceData>> {
+    // also returns the tag query made
+    fn forward_search(report: &MsgReport, md: &[TraceData], warrant: &TraceWarrant) -> (Vec<Vec<TraceData>>, TagQuery) {
+        let input_msg = &report.payload;
         let mut result: Vec<Vec<TraceData>> = Vec::new();
-        let users: Vec<u32> = md.into_iter().map(|data| data.uid).collect();
-        let (vec_vec_nbrs, map_id_ik) = db_query_nbrs(&users);
+        let users: Vec<Uid> = md.iter().map(|data| data.uid).collect();
+        let (vec_vec_nbrs, map_id_ik, map_gid_hist) = db_query_nbrs(report, &users, warrant);
         let mut pack_tags_tbt: Vec<Vec<String>> = Vec::new();
         let mut pack_next_key_set: Vec<Vec<[u8; 16]>> = Vec::new();
         for i in 0..vec_vec_nbrs.len() {
             let vec_nbrs = vec_vec_nbrs.get(i).unwrap();
-            let curr_uik = &map_id_ik.get(&md.get(i).unwrap().uid).unwrap();
+            let curr_uid = md.get(i).unwrap().uid;
+            // a user without a key has no nbrs in the query
+            let curr_uik = map_id_ik.get(&curr_uid).copied().unwrap_or_default();
             let key = Arc::new(md.get(i).unwrap().key);
             let message = Arc::new(input_msg.clone());
             let par_tags: Arc<Mutex<HashMap<usize, String>>> = Arc::new(Mutex::new(HashMap::new()));
             let par_next_keys: Arc<Mutex<HashMap<usize, [u8;16]>>> = Arc::new(Mutex::new(HashMap::new()));
             let mut thread_list = Vec::new();
             for j in 0..vec_nbrs.len() {
-                let curr_nbr_id: &u32 = vec_nbrs.get(j).unwrap();
+                let curr_nbr_id: &Uid = vec_nbrs.get(j).unwrap();
                 let tags_hmap: Arc<Mutex<HashMap<usize, String>>> = par_tags.clone();
                 let next_key_hmap: Arc<Mutex<HashMap<usize, [u8; 16]>>> = par_next_keys.clone();
-                let tk: [u8; 16] = algos::tk_gen(&curr_uik, &curr_nbr_id);
+                let tk: [u8; 16] = match map_gid_hist.contains_key(curr_nbr_id) {
+                    true => algos::group_tk_gen(map_id_ik.get(curr_nbr_id).unwrap(), &curr_uid),
+                    false => algos::tk_gen(&curr_uik, &curr_nbr_id),
+                };
                 let lock_key: Arc<[u8; 16]> = Arc::clone(&key);
                 let lock_message: Arc<String> = Arc::clone(&message);
 
@@ -154,7 +278,20 @@ pub mod traceback {
             pack_tags_tbt.push(tags_tbt);
             pack_next_key_set.push(next_key_set);
         }
+        let query = TagQuery::of(&pack_tags_tbt.concat());
         let vec_resp: Vec<Vec<bool>> = db_tag::mexists_pack(&pack_tags_tbt);
+        let mut group_tags: Vec<String> = Vec::new();
+        for (i, response) in vec_resp.iter().enumerate() {
+            for (j, found) in response.iter().enumerate() {
+                if *found & map_gid_hist.contains_key(&vec_vec_nbrs[i][j]) {
+                    group_tags.push(pack_tags_tbt[i][j].clone());
+                }
+            }
+        }
+        let map_tag_ts = match group_tags.is_empty() {
+            true => HashMap::new(),
+            false => group_send_ts(group_tags),
+        };
         for i in 0..vec_resp.len() {
             let next_key_set = pack_next_key_set.get(i).unwrap();
             let response = vec_resp.get(i).unwrap();
@@ -165,72 +302,891 @@ pub mod traceback {
                 if *response.get(j).unwrap() == true {
                     let nbr_id = vec_nbrs.get(j).unwrap();
                     let next_key = next_key_set.get(j).unwrap();
-                    rcv_result.push(TraceData {uid: *nbr_id, key: *next_key})
+                    match map_gid_hist.get(nbr_id) {
+                        // a group send reaches every other member at the time it was sent, with the same key
+                        Some(history) => db_grp::members_in(history, &map_tag_ts[&pack_tags_tbt[i][j]]).iter()
+                            .filter(|member| **member != md.get(i).unwrap().uid)
+                            .for_each(|member| rcv_result.push(TraceData::via_group(*member, *next_key, *nbr_id))),
+                        None => rcv_result.push(TraceData::new(*nbr_id, *next_key)),
+                    }
                 }
             }
             result.push(rcv_result);
         }
-        result
+        (result, query)
     }
 
-    pub fn tracing(report: &MsgReport, snd_start: &u32) -> Vec<Edge>{
-        let mut path: Vec<Edge> = Vec::new();
-        let mut current_sender= TraceData { uid: *snd_start, key: report.key };
-        let mut rcv_set: Vec<TraceData> = Vec::new();
-        let mut searched_rcv: HashSet<String> = HashSet::new();
-        rcv_set.push(current_sender.clone());
+    // map device-level edges to their owners, dropping forwards between a user's own devices
+    pub fn collapse_devices(dev_path: &[Edge]) -> Vec<Edge> {
+        let mut devices: Vec<Uid> = dev_path.iter().flat_map(|e| [e.sid, e.rid]).collect();
+        devices.sort();
+        devices.dedup();
+        let map_owner = db_dev::owner(&devices);
 
-        while (current_sender.uid != 0) | (rcv_set.is_empty() == false) {
-            // Search the acestor of the sender
-            if current_sender.uid != 0 {
-                let prev_sender: TraceData;
-                prev_sender = par_backward_search(&report.payload, &TraceData { uid: current_sender.uid, key: current_sender.key });
-                if prev_sender.uid != 0 {
-                    path.push(Edge::new(&prev_sender.uid, &current_sender.uid));
-                }
-                rcv_set.push(current_sender.clone());
-                current_sender = TraceData::from(prev_sender);
+        let mut user_path: Vec<Edge> = Vec::new();
+        for e in dev_path {
+            let (snd, rcv) = (map_owner.get(&e.sid).unwrap(), map_owner.get(&e.rid).unwrap());
+            let is_new = !user_path.iter().any(|u| (u.sid, u.rid, u.gid) == (*snd, *rcv, e.gid));
+            (snd != rcv && is_new).then(|| user_path.push(hop_edge(snd, rcv, &e.gid)));
+        }
+        user_path
+    }
+
+    // trace from a reporting device, returning (user-level edges, device-level edges)
+    pub fn tracing_by_user(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant) -> Result<(Vec<Edge>, Vec<Edge>), WarrantError> {
+        let dev_path = tracing(report, snd_start, warrant)?;
+        Ok((collapse_devices(&dev_path), dev_path))
+    }
+
+    // Every trace runs under a warrant for its report, which opens the identity keys
+    // and fixes the scope through its grant
+    pub fn tracing(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant) -> Result<Vec<Edge>, WarrantError> {
+        let (edges, _) = tracing_with_ambiguity(report, snd_start, warrant)?;
+        Ok(edges)
+    }
+
+    // Return the hops that had more than one candidate predecessor beside the edges.
+    // Without limits only a failed audit log stops the trace early, and partial edges are never handed out for a full trace
+    pub fn tracing_with_ambiguity(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant) -> Result<(Vec<Edge>, Vec<Ambiguity>), WarrantError> {
+        let state = trace_run(TraceState::new(report, snd_start, warrant)?, report, warrant, &TraceLimits::unlimited());
+        state.audit_finished()?;
+        if !state.is_done() {
+            return Err(WarrantError::Storage("a tag query could not be logged, the trace stopped before it was done".to_string()));
+        }
+        Ok((state.edges.iter().map(|e| hop_edge(&e.sid, &e.rid, &e.gid)).collect(), state.ambiguity))
+    }
+
+    // the recovered tag keys are sealed to the tracing authority in the result
+    pub fn tracing_result(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant, ta_pk: &PublicKey) -> Result<TraceResult, WarrantError> {
+        Ok(tracing_limited(report, snd_start, warrant, &TraceLimits::unlimited(), ta_pk)?.0)
+    }
+
+    // stop once a limit is hit, the result is then marked incomplete and the state can be resumed
+    pub fn tracing_limited(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant, limits: &TraceLimits, ta_pk: &PublicKey) -> Result<(TraceResult, SealedTraceState), WarrantError> {
+        resume_tracing(report, TraceState::new(report, snd_start, warrant)?, warrant, limits, ta_pk)
+    }
+
+    // the state is returned sealed to the tracing authority, since it holds the recovered tag keys
+    pub fn resume_tracing(report: &MsgReport, mut state: TraceState, warrant: &TraceWarrant, limits: &TraceLimits, ta_pk: &PublicKey) -> Result<(TraceResult, SealedTraceState), WarrantError> {
+        state.resume(report, warrant)?;
+        let state = trace_run(state, report, warrant, limits);
+        state.audit_finished()?;
+        Ok((TraceResult::from_state(&state, ta_pk), state.seal(ta_pk)))
+    }
+
+    // depth, visited users and tag lookups count over the whole trace, the timeout for each call
+    #[derive(Clone, Debug, Default)]
+    pub struct TraceLimits {
+        // number of BFS rounds
+        pub max_depth: Option<usize>,
+        pub max_visited: Option<usize>,
+        pub max_tag_lookups: Option<usize>,
+        pub timeout: Option<Duration>,
+    }
+
+    impl TraceLimits {
+        pub fn unlimited() -> Self {
+            TraceLimits::default()
+        }
+
+        fn is_unlimited(&self) -> bool {
+            self.max_depth.is_none() & self.max_visited.is_none() & self.max_tag_lookups.is_none() & self.timeout.is_none()
+        }
+
+        pub(crate) fn reached(&self, stats: &TraceStats, t_start: &Instant) -> bool {
+            self.max_depth.is_some_and(|max| stats.rounds >= max)
+                | self.max_visited.is_some_and(|max| stats.visited_users >= max)
+                | self.max_tag_lookups.is_some_and(|max| stats.tag_lookups >= max)
+                | self.timeout.is_some_and(|max| t_start.elapsed() >= max)
+        }
+    }
+
+    // the limits of one call from the time it started, checked before every search step of a round
+    pub(crate) struct TraceBudget<'a> {
+        limits: &'a TraceLimits,
+        t_start: Instant,
+    }
+
+    const UNLIMITED: TraceLimits = TraceLimits { max_depth: None, max_visited: None, max_tag_lookups: None, timeout: None };
+
+    // senders searched forward per batch when a round may be cut short
+    const FORWARD_BATCH: usize = 64;
+
+    impl<'a> TraceBudget<'a> {
+        pub(crate) fn new(limits: &'a TraceLimits) -> Self {
+            TraceBudget { limits, t_start: Instant::now() }
+        }
+
+        pub(crate) fn unlimited() -> Self {
+            TraceBudget::new(&UNLIMITED)
+        }
+
+        pub(crate) fn spent(&self, stats: &TraceStats) -> bool {
+            self.limits.reached(stats, &self.t_start)
+        }
+
+        pub(crate) fn elapsed_ms(&self) -> u128 {
+            self.t_start.elapsed().as_millis()
+        }
+    }
+
+    // a TraceState sealed to the tracing authority, open it to resume the trace
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct SealedTraceState {
+        pub reporter: Uid,
+        sealed: SealedData,
+    }
+
+    impl SealedTraceState {
+        pub fn open(&self, ta: &TraceAuthority) -> Option<TraceState> {
+            serde_json::from_slice(&ta.open_data(&self.sealed)?).ok()
+        }
+    }
+
+    // the search between BFS rounds, together with everything found so far
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct TraceState {
+        pub reporter: Uid,
+        // fixed by the grant the trace was started with, and checked against it again on resume
+        scope: TraceScope,
+        // whether the senders found are searched for receivers
+        forward: bool,
+        // kept in the checkpoint so a resumed trace keeps logging for the same operator
+        audit: AuditContext,
+        // a tag query could not be logged, the trace stops here until it is resumed
+        #[serde(skip)]
+        halted: bool,
+        // the fields below hold the users of the chain, which only the scope decides to hand out
+        bwd_set: Vec<TraceData>,
+        rcv_set: Vec<TraceData>,
+        searched_bwd: HashSet<String>,
+        searched_rcv: HashSet<String>,
+        // receivers found by the forward level in progress, searched once every sender of the level is
+        next_level: HashSet<String>,
+        visited: HashSet<Uid>,
+        // users searched on a chain the scope hides, only counted
+        hidden_visits: usize,
+        edges: Vec<TraceEdge>,
+        nodes: Vec<TraceData>,
+        origins: Vec<Uid>,
+        ambiguity: Vec<Ambiguity>,
+        stats: TraceStats,
+    }
+
+    impl TraceState {
+        pub fn new(report: &MsgReport, snd_start: &Uid, warrant: &TraceWarrant) -> Result<Self, WarrantError> {
+            warrant.check(report)?;
+            let start = TraceData::new(*snd_start, report.key);
+            let grant = warrant.grant();
+            let scope = grant.scope();
+            // a subtree rooted at the reporter needs no backward search
+            let (bwd_set, rcv_set) = match scope {
+                TraceScope::ForwardSubtree(root) if root == *snd_start => (Vec::new(), vec![start]),
+                _ => (vec![start], Vec::new()),
+            };
+            let audit = AuditContext::start(grant.operator(), report, &scope).map_err(storage)?;
+            Ok(TraceState {
+                reporter: *snd_start,
+                scope,
+                forward: !rcv_set.is_empty() | (scope == TraceScope::FullSpread),
+                audit,
+                halted: false,
+                bwd_set,
+                rcv_set,
+                searched_bwd: HashSet::new(),
+                searched_rcv: HashSet::new(),
+                next_level: HashSet::new(),
+                visited: HashSet::new(),
+                hidden_visits: 0,
+                edges: Vec::new(),
+                nodes: Vec::new(),
+                origins: Vec::new(),
+                ambiguity: Vec::new(),
+                stats: TraceStats::default(),
+            })
+        }
+
+        pub fn scope(&self) -> TraceScope {
+            self.scope
+        }
+
+        pub fn stats(&self) -> &TraceStats {
+            &self.stats
+        }
+
+        pub(crate) fn add_runtime(&mut self, elapsed_ms: u128) {
+            self.stats.runtime_ms += elapsed_ms;
+        }
+
+        // the users between the origin and the reporter are only revealed by these scopes
+        fn reveals_chain(&self) -> bool {
+            matches!(self.scope, TraceScope::PathToOrigin | TraceScope::FullSpread)
+        }
+
+        fn visited_users(&self) -> usize {
+            self.visited.len() + self.hidden_visits
+        }
+
+        // A state read back from a checkpoint is only resumed within the grant of the warrant,
+        // a forward search is only allowed by full spread or once the subtree root is found
+        pub(crate) fn permitted_by(&self, grant: &ScopeGrant) -> bool {
+            let forward_ok = match self.scope {
+                TraceScope::OriginOnly | TraceScope::PathToOrigin => !self.forward & self.rcv_set.is_empty(),
+                TraceScope::ForwardSubtree(root) => match self.forward {
+                    true => self.rcv_set.iter().chain(&self.nodes).any(|td| td.uid == root),
+                    false => self.rcv_set.is_empty(),
+                },
+                TraceScope::FullSpread => self.forward,
+            };
+            (self.scope == grant.scope()) & forward_ok
+        }
+
+        // checked by every call that continues the trace, a halted trace tries the audit log again
+        pub(crate) fn resume(&mut self, report: &MsgReport, warrant: &TraceWarrant) -> Result<(), WarrantError> {
+            warrant.check(report)?;
+            match self.permitted_by(warrant.grant()) & self.audit.is_for(warrant.grant().operator(), report) {
+                true => {
+                    self.halted = false;
+                    Ok(())
+                },
+                false => Err(WarrantError::ScopeNotPermitted),
             }
+        }
 
-            // Search the receivers of the message
-            let rcv_len_at_begin = rcv_set.len();
-            if rcv_set.is_empty() == false {
-                let mut outside_set: Vec<TraceData> = Vec::new();
-                let bf_results = par_forward_search(&&report.payload, &rcv_set);
-                for i in 0..bf_results.len() {
-                    let mut inside_set: Vec<TraceData> = bf_results.get(i).unwrap().to_vec();
-                    let sender = rcv_set.get(i).unwrap();
-                    // find searched node
-                    let mut remove_index: Vec<usize> = Vec::new();
-                    for i in 0..inside_set.len() {
-                        let rcv = inside_set.get(i).unwrap();
-                        if searched_rcv.contains(&rcv.hash()) {
-                            // inside_set.remove(i);
-                            remove_index.push(i);
-                        }
+        pub fn seal(&self, ta_pk: &PublicKey) -> SealedTraceState {
+            SealedTraceState { reporter: self.reporter, sealed: SealedData::seal(&serde_json::to_vec(self).unwrap(), ta_pk) }
+        }
+
+        // a digest of what the trace has revealed so far
+        pub fn digest(&self) -> [u8; 16] {
+            hash(&serde_json::to_string(&(&self.origins, &self.edges)).unwrap())
+        }
+
+        // no result is handed out before its digest is in the audit log
+        pub(crate) fn audit_finished(&self) -> Result<(), WarrantError> {
+            self.audit.finished(self.is_done(), &self.digest()).map_err(storage)
+        }
+
+        pub fn is_done(&self) -> bool {
+            self.bwd_set.is_empty() & self.rcv_set.is_empty()
+        }
+
+        pub fn is_halted(&self) -> bool {
+            self.halted
+        }
+
+        // users still to be searched, none when the scope hides the chain
+        pub fn frontier(&self) -> Vec<Uid> {
+            if !self.reveals_chain() {
+                return Vec::new();
+            }
+            let mut uids: Vec<Uid> = self.bwd_set.iter().chain(&self.rcv_set).map(|td| td.uid).collect();
+            uids.sort();
+            uids.dedup();
+            uids
+        }
+
+        pub fn frontier_len(&self) -> usize {
+            self.bwd_set.len() + self.rcv_set.len()
+        }
+
+        pub(crate) fn result(&self, ta_pk: &PublicKey) -> TraceResult {
+            let mut result = TraceResult::new(&self.reporter, self.origins.clone(), self.edges.clone(), &self.nodes, &self.ambiguity, self.stats.clone(), ta_pk);
+            result.scope = self.scope;
+            result.complete = self.is_done();
+            result.frontier = self.frontier();
+            result.frontier_len = self.frontier_len();
+            result
+        }
+
+        // explore every candidate predecessor, recording each edge with its search direction
+        pub(crate) fn round(&mut self, report: &MsgReport, warrant: &TraceWarrant) {
+            self.round_within(report, warrant, &TraceBudget::unlimited());
+        }
+
+        // A round cut short by the budget still counts as a round,
+        // the users it did not search stay on the frontier for the next one
+        pub(crate) fn round_within(&mut self, report: &MsgReport, warrant: &TraceWarrant, budget: &TraceBudget) {
+            self.backward_phase(report, warrant, budget, &mut |_| {});
+            self.forward_phase(report, warrant, budget, &mut |_| {});
+        }
+
+        // Search the acestors of the senders
+        pub(crate) fn backward_phase(&mut self, report: &MsgReport, warrant: &TraceWarrant, budget: &TraceBudget, emit: &mut dyn FnMut(TraceEvent)) {
+            // while looking for the root of a forward subtree the chain itself stays hidden
+            let subtree_root = match self.scope {
+                TraceScope::ForwardSubtree(root) if !self.forward => Some(root),
+                _ => None,
+            };
+            let reveal_chain = self.reveals_chain();
+            let mut next_bwd_set: Vec<TraceData> = Vec::new();
+            let mut next_bwd_keys: HashSet<String> = HashSet::new();
+            let mut roots: Vec<TraceData> = Vec::new();
+            let mut root_keys: HashSet<String> = HashSet::new();
+            let bwd_set = std::mem::take(&mut self.bwd_set);
+            let mut searched = 0;
+            for current_sender in &bwd_set {
+                if budget.spent(&self.stats) | self.halted {
+                    break;
+                }
+                let (prev_senders, query) = backward_search(report, current_sender, warrant);
+                // the answer of an unlogged query is dropped, the sender is searched again on resume
+                if !record_query(&mut self.stats, &self.audit, &query) {
+                    self.halted = true;
+                    break;
+                }
+                searched += 1;
+                self.searched_bwd.insert(current_sender.hash());
+                let is_origin = prev_senders.is_empty() & subtree_root.is_none();
+                if is_origin {
+                    self.origins.push(current_sender.uid);
+                    emit(TraceEvent::OriginReached { uid: current_sender.uid, stats: self.stats.clone() });
+                }
+                (reveal_chain & (prev_senders.len() > 1)).then(|| self.ambiguity.push(Ambiguity { uid: current_sender.uid, candidates: prev_senders.clone() }));
+                for prev_sender in prev_senders {
+                    if reveal_chain {
+                        let edge = TraceEdge::new(&prev_sender.uid, &current_sender.uid, &prev_sender.gid, SearchDir::Backward);
+                        emit(TraceEvent::AncestorFound { edge: edge.clone(), stats: self.stats.clone() });
+                        self.edges.push(edge);
                     }
+                    let key = prev_sender.hash();
+                    if (subtree_root == Some(prev_sender.uid)) && root_keys.insert(key.clone()) {
+                        roots.push(prev_sender.clone());
+                    }
+                    if !self.searched_bwd.contains(&key) && next_bwd_keys.insert(key) {
+                        next_bwd_set.push(prev_sender);
+                    }
+                }
+                match reveal_chain | is_origin {
+                    true => { self.visited.insert(current_sender.uid); },
+                    false => self.hidden_visits += 1,
+                }
+                self.stats.visited_users = self.visited_users();
+                if self.forward {
+                    self.rcv_set.push(current_sender.clone());
+                } else if reveal_chain | is_origin {
+                    self.nodes.push(current_sender.clone());
+                }
+            }
+            // senders the budget did not reach go first next round
+            self.bwd_set = bwd_set[searched..].to_vec();
+            let left: HashSet<String> = self.bwd_set.iter().map(TraceData::hash).collect();
+            next_bwd_set.retain(|td| !left.contains(&td.hash()));
+            self.bwd_set.extend(next_bwd_set);
+            // the root is found, so only its receivers are searched from now on
+            if !roots.is_empty() {
+                self.bwd_set.clear();
+                self.rcv_set = roots;
+                self.forward = true;
+            }
+        }
+
+        // Search the receivers of the message, in batches when the budget may cut the round short.
+        // A receiver gets an edge from every sender of the level that reached it, however the level is batched
+        pub(crate) fn forward_phase(&mut self, report: &MsgReport, warrant: &TraceWarrant, budget: &TraceBudget, emit: &mut dyn FnMut(TraceEvent)) {
+            let level: Vec<TraceData> = self.rcv_set.iter().filter(|td| !self.next_level.contains(&td.hash())).cloned().collect();
+            let level_keys: HashSet<String> = level.iter().map(TraceData::hash).collect();
+            let batch = match budget.limits.is_unlimited() {
+                true => level.len(),
+                false => FORWARD_BATCH,
+            };
+            let mut searched = 0;
+            while (searched < level.len()) && !budget.spent(&self.stats) && !self.halted {
+                let senders = &level[searched..(searched + batch).min(level.len())];
+                let (bf_results, query) = forward_search(report, senders, warrant);
+                if !record_query(&mut self.stats, &self.audit, &query) {
+                    self.halted = true;
+                    break;
+                }
+                searched += senders.len();
+                for (sender, receivers) in senders.iter().zip(bf_results) {
                     // remove searched node
-                    for i in &remove_index {
-                        inside_set.remove(*i);
+                    let receivers: Vec<TraceData> = receivers.into_iter()
+                        .filter(|rcv| !self.searched_rcv.contains(&rcv.hash()) & !level_keys.contains(&rcv.hash()))
+                        .collect();
+                    let batch: Vec<TraceEdge> = receivers.iter()
+                        .map(|in_td| TraceEdge::new(&sender.uid, &in_td.uid, &in_td.gid, SearchDir::Forward))
+                        .collect();
+                    if !batch.is_empty() {
+                        self.edges.extend(batch.clone());
+                        emit(TraceEvent::ReceiverBatch { sid: sender.uid, edges: batch, stats: self.stats.clone() });
                     }
-                    // if first found, then put in path
-                    if inside_set.is_empty() == false {
-                        for in_td in &inside_set {
-                            path.push(Edge::new(&sender.uid, &in_td.uid))
+                    for rcv in receivers {
+                        if self.next_level.insert(rcv.hash()) {
+                            self.rcv_set.push(rcv);
                         }
-                        outside_set.extend(inside_set);
                     }
                 }
-                rcv_set.extend(outside_set);
+                for user in senders {
+                    self.searched_rcv.insert(user.hash());
+                    self.visited.insert(user.uid);
+                }
+                self.stats.visited_users = self.visited_users();
+            }
+            // pop the receivers that already search, the next level starts once this one is done
+            let done: HashSet<String> = level[..searched].iter().map(TraceData::hash).collect();
+            let (prev_rcv_set, rcv_set): (Vec<TraceData>, Vec<TraceData>) = std::mem::take(&mut self.rcv_set).into_iter().partition(|td| done.contains(&td.hash()));
+            self.rcv_set = rcv_set;
+            self.nodes.extend(prev_rcv_set);
+            if searched == level.len() {
+                self.next_level.clear();
+            }
+            self.stats.rounds += 1;
+            self.stats.visited_users = self.visited_users();
+            self.stats.ambiguous_hops = self.ambiguity.len();
+            emit(TraceEvent::RoundComplete { round: self.stats.rounds, frontier: self.frontier(), frontier_len: self.frontier_len(), stats: self.stats.clone() });
+        }
+    }
+
+    // whether the query is in the audit log
+    fn record_query(stats: &mut TraceStats, audit: &AuditContext, query: &TagQuery) -> bool {
+        let logged = (query.lookups == 0) || audit.tag_query(query).is_ok();
+        if logged {
+            stats.tag_lookups += query.lookups;
+        }
+        logged
+    }
+
+    fn storage(error: redis::RedisError) -> WarrantError {
+        WarrantError::Storage(error.to_string())
+    }
+
+    fn trace_run(mut state: TraceState, report: &MsgReport, warrant: &TraceWarrant, limits: &TraceLimits) -> TraceState {
+        let budget = TraceBudget::new(limits);
+        while !state.is_done() & !budget.spent(&state.stats) & !state.halted {
+            state.round_within(report, warrant, &budget);
+        }
+        state.add_runtime(budget.elapsed_ms());
+        state
+    }
+}
+
+pub mod trace_result {
+    use std::collections::{HashMap, HashSet, VecDeque};
+
+    use petgraph::{dot::Dot, graph::{Graph, NodeIndex}};
+    use serde::{Serialize, Deserialize};
+    use x25519_dalek::PublicKey;
+
+    use crate::message::messaging::{Edge, Uid};
+    use crate::message::sealed_sender::SealedBox;
+    use crate::trace::traceback::{TraceData, Ambiguity, TraceState};
+    use crate::trace::trace_scope::TraceScope;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum SearchDir {
+        Backward,
+        Forward,
+    }
+
+    // a directed forwarding edge, where depth is the number of hops from the origin to rid
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct TraceEdge {
+        pub sid: Uid,
+        pub rid: Uid,
+        pub gid: Option<Uid>,
+        pub depth: usize,
+        pub dir: SearchDir,
+    }
+
+    impl TraceEdge {
+        pub fn new(sid: &Uid, rid: &Uid, gid: &Option<Uid>, dir: SearchDir) -> Self {
+            TraceEdge { sid: *sid, rid: *rid, gid: *gid, depth: 0, dir }
+        }
+    }
+
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct TraceStats {
+        pub rounds: usize,
+        pub visited_users: usize,
+        pub tag_lookups: usize,
+        pub ambiguous_hops: usize,
+        pub runtime_ms: u128,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct SealedNodeKey {
+        pub uid: Uid,
+        pub key: SealedBox,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct AmbiguousHop {
+        pub uid: Uid,
+        pub candidates: Vec<Uid>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct TraceResult {
+        pub reporter: Uid,
+        pub scope: TraceScope,
+        // more than one origin only when backward search was ambiguous
+        pub origins: Vec<Uid>,
+        pub edges: Vec<TraceEdge>,
+        pub node_keys: Vec<SealedNodeKey>,
+        pub ambiguous_hops: Vec<AmbiguousHop>,
+        pub stats: TraceStats,
+        // false when a trace limit stopped the search, frontier then holds the unexplored users
+        // if the scope reveals the chain, and only their number otherwise
+        pub complete: bool,
+        pub frontier: Vec<Uid>,
+        pub frontier_len: usize,
+    }
+
+    impl TraceResult {
+        pub fn new(reporter: &Uid, origins: Vec<Uid>, mut edges: Vec<TraceEdge>, nodes: &[TraceData], ambiguity: &[Ambiguity], stats: TraceStats, ta_pk: &PublicKey) -> Self {
+            let node_depth = bfs_depth(&origins, &edges);
+            edges.iter_mut().for_each(|e| e.depth = node_depth.get(&e.sid).map_or(1, |d| d + 1));
+            let node_keys = nodes.iter()
+                .map(|td| SealedNodeKey { uid: td.uid, key: SealedBox::seal_key(&td.key, ta_pk) })
+                .collect();
+            let ambiguous_hops = ambiguity.iter()
+                .map(|amb| AmbiguousHop { uid: amb.uid, candidates: amb.candidates.iter().map(|td| td.uid).collect() })
+                .collect();
+            TraceResult { reporter: *reporter, scope: TraceScope::FullSpread, origins, edges, node_keys, ambiguous_hops, stats, complete: true, frontier: Vec::new(), frontier_len: 0 }
+        }
+
+        pub fn from_state(state: &TraceState, ta_pk: &PublicKey) -> Self {
+            state.result(ta_pk)
+        }
+
+        pub fn origin(&self) -> Option<Uid> {
+            match self.origins.len() {
+                1 => self.origins.first().copied(),
+                _ => None,
+            }
+        }
+
+        pub fn to_edges(&self) -> Vec<Edge> {
+            self.edges.iter().map(|e| match e.gid {
+                Some(gid) => Edge::via_group(&e.sid, &gid, &e.rid),
+                None => Edge::new(&e.sid, &e.rid),
+            }).collect()
+        }
+
+        pub fn to_json(&self) -> String {
+            serde_json::to_string_pretty(self).unwrap()
+        }
+
+        pub fn to_dot(&self) -> String {
+            let mut graph = Graph::<String, String>::new();
+            let mut node_index = HashMap::<Uid, NodeIndex>::new();
+            for e in &self.edges {
+                for uid in [e.sid, e.rid] {
+                    node_index.entry(uid).or_insert_with(|| {
+                        let label = match (self.origins.contains(&uid), uid == self.reporter) {
+                            (true, _) => format!("U{} (origin)", uid),
+                            (_, true) => format!("U{} (reporter)", uid),
+                            _ => format!("U{}", uid),
+                        };
+                        graph.add_node(label)
+                    });
+                }
+                let dir = match e.dir {
+                    SearchDir::Backward => "bwd",
+                    SearchDir::Forward => "fwd",
+                };
+                let label = match e.gid {
+                    Some(gid) => format!("{} {} G{}", e.depth, dir, gid),
+                    None => format!("{} {}", e.depth, dir),
+                };
+                graph.add_edge(node_index[&e.sid], node_index[&e.rid], label);
+            }
+            format!("{}", Dot::new(&graph))
+        }
+    }
+
+    // hop distance of each node from the origins, where nodes unreachable from them start at 0
+    fn bfs_depth(origins: &[Uid], edges: &[TraceEdge]) -> HashMap<Uid, usize> {
+        let mut children = HashMap::<Uid, Vec<Uid>>::new();
+        edges.iter().for_each(|e| children.entry(e.sid).or_default().push(e.rid));
+        let receivers: HashSet<Uid> = edges.iter().map(|e| e.rid).collect();
+        let mut roots: Vec<Uid> = origins.to_vec();
+        for e in edges {
+            if !receivers.contains(&e.sid) & !roots.contains(&e.sid) {
+                roots.push(e.sid);
+            }
+        }
+
+        let mut depth = HashMap::<Uid, usize>::new();
+        let mut queue: VecDeque<Uid> = VecDeque::new();
+        roots.into_iter().for_each(|root| {
+            depth.insert(root, 0);
+            queue.push_back(root);
+        });
+        while let Some(node) = queue.pop_front() {
+            let d = depth[&node];
+            for child in children.get(&node).into_iter().flatten() {
+                if !depth.contains_key(child) {
+                    depth.insert(*child, d + 1);
+                    queue.push_back(*child);
+                }
+            }
+        }
+        depth
+    }
+}
+
+pub mod trace_scope {
+    use serde::{Serialize, Deserialize};
+    use crate::message::messaging::Uid;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum TraceScope {
+        // only the originator, found by the backward chain
+        OriginOnly,
+        // the backward chain from the reporter to the originator
+        PathToOrigin,
+        // the receivers downstream of a user on the backward chain
+        ForwardSubtree(Uid),
+        FullSpread,
+    }
+
+    // the scopes a moderator may run beside origin only
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct ScopePolicy {
+        pub path_to_origin: bool,
+        pub forward_subtree: bool,
+        pub full_spread: bool,
+    }
+
+    impl ScopePolicy {
+        pub fn origin_only() -> Self {
+            ScopePolicy::default()
+        }
+
+        pub fn all() -> Self {
+            ScopePolicy { path_to_origin: true, forward_subtree: true, full_spread: true }
+        }
+
+        pub fn permits(&self, scope: &TraceScope) -> bool {
+            match scope {
+                TraceScope::OriginOnly => true,
+                TraceScope::PathToOrigin => self.path_to_origin,
+                TraceScope::ForwardSubtree(_) => self.forward_subtree,
+                TraceScope::FullSpread => self.full_spread,
+            }
+        }
+
+        // called by the moderator set holding the policy when it issues a warrant,
+        // the operator is written to the audit log by every trace run with the grant
+        pub(crate) fn authorize(&self, scope: TraceScope, operator: &str) -> Option<ScopeGrant> {
+            self.permits(&scope).then(|| ScopeGrant { scope, operator: operator.to_string() })
+        }
+    }
+
+    // only a policy can issue a grant, every trace runs with the one carried by its warrant
+    #[derive(Clone, Debug)]
+    pub struct ScopeGrant {
+        scope: TraceScope,
+        operator: String,
+    }
+
+    impl ScopeGrant {
+        pub fn scope(&self) -> TraceScope {
+            self.scope
+        }
+
+        pub fn operator(&self) -> &str {
+            &self.operator
+        }
+    }
+}
+
+pub mod trace_job {
+    use std::{fs, io, path::{Path, PathBuf}};
+
+    use serde::{Serialize, Deserialize};
+    use x25519_dalek::PublicKey;
+
+    use crate::message::messaging::{MsgReport, Uid};
+    use crate::message::sealed_sender::{SealedData, TraceAuthority};
+    use crate::trace::traceback::{TraceState, TraceLimits, TraceBudget};
+    use crate::trace::trace_result::TraceResult;
+    use crate::warrant::trace_warrant::{TraceWarrant, WarrantError};
+
+    // a trace that checkpoints its state after every BFS round, so another process can pick it up
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct TraceJob {
+        pub report: MsgReport,
+        state: TraceState,
+        // the checkpoint file and the key it is sealed to
+        #[serde(skip)]
+        checkpoint_to: Option<(PathBuf, PublicKey)>,
+    }
+
+    fn invalid_data(error: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+
+    fn warrant_error(error: WarrantError) -> io::Error {
+        let error = match error {
+            WarrantError::Storage(error) => return io::Error::other(error),
+            WarrantError::NotCovered => "the warrant is for another report",
+            _ => "the warrant does not grant the scope of the job",
+        };
+        io::Error::new(io::ErrorKind::PermissionDenied, error)
+    }
+
+    impl TraceJob {
+        pub fn new(report: MsgReport, snd_start: &Uid, warrant: &TraceWarrant) -> Result<Self, WarrantError> {
+            let state = TraceState::new(&report, snd_start, warrant)?;
+            Ok(TraceJob { report, state, checkpoint_to: None })
+        }
+
+        pub fn with_checkpoint<P: AsRef<Path>>(mut self, path: P, ta_pk: &PublicKey) -> Self {
+            self.checkpoint_to = Some((path.as_ref().to_path_buf(), *ta_pk));
+            self
+        }
+
+        pub fn is_done(&self) -> bool {
+            self.state.is_done()
+        }
+
+        pub fn state(&self) -> &TraceState {
+            &self.state
+        }
+
+        // run one round, then write the checkpoint
+        pub fn step(&mut self, warrant: &TraceWarrant) -> io::Result<()> {
+            self.state.resume(&self.report, warrant).map_err(warrant_error)?;
+            self.step_within(warrant, &TraceBudget::unlimited())
+        }
+
+        fn step_within(&mut self, warrant: &TraceWarrant, budget: &TraceBudget) -> io::Result<()> {
+            if !self.state.is_done() {
+                self.state.round_within(&self.report, warrant, budget);
+            }
+            match &self.checkpoint_to {
+                Some((path, ta_pk)) => self.save(path, ta_pk),
+                None => Ok(()),
+            }
+        }
+
+        pub fn run(&mut self, warrant: &TraceWarrant, limits: &TraceLimits) -> io::Result<()> {
+            self.state.resume(&self.report, warrant).map_err(warrant_error)?;
+            let budget = TraceBudget::new(limits);
+            while !self.state.is_done() & !budget.spent(self.state.stats()) & !self.state.is_halted() {
+                self.step_within(warrant, &budget)?;
+            }
+            self.state.add_runtime(budget.elapsed_ms());
+            self.state.audit_finished().map_err(warrant_error)
+        }
+
+        pub fn result(&self, ta_pk: &PublicKey) -> TraceResult {
+            TraceResult::from_state(&self.state, ta_pk)
+        }
+
+        // sealed to the tracing authority, since it holds the report and the recovered tag keys
+        pub fn checkpoint(&self, ta_pk: &PublicKey) -> String {
+            let sealed = SealedData::seal(&serde_json::to_vec(self).unwrap(), ta_pk);
+            serde_json::to_string(&sealed).unwrap()
+        }
+
+        pub fn from_checkpoint(checkpoint: &str, ta: &TraceAuthority) -> io::Result<Self> {
+            let sealed: SealedData = serde_json::from_str(checkpoint)?;
+            let job = ta.open_data(&sealed).ok_or_else(|| invalid_data("checkpoint is not sealed to this authority"))?;
+            Ok(serde_json::from_slice(&job)?)
+        }
+
+        // written to a temporary file first so a crash never leaves a torn checkpoint
+        pub fn save<P: AsRef<Path>>(&self, path: P, ta_pk: &PublicKey) -> io::Result<()> {
+            let path = path.as_ref();
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp, self.checkpoint(ta_pk))?;
+            fs::rename(tmp, path)
+        }
+
+        // resume from a checkpoint, continuing to checkpoint into the same file
+        pub fn load<P: AsRef<Path>>(path: P, ta: &TraceAuthority) -> io::Result<Self> {
+            let checkpoint = fs::read_to_string(&path)?;
+            let job = TraceJob::from_checkpoint(&checkpoint, ta)?;
+            Ok(job.with_checkpoint(path, &ta.public))
+        }
+    }
+}
+
+pub mod trace_stream {
+    use std::collections::VecDeque;
+
+    use serde::{Serialize, Deserialize};
+
+    use crate::message::messaging::{MsgReport, Uid};
+    use crate::trace::traceback::{TraceState, TraceBudget};
+    use crate::trace::trace_result::{TraceEdge, TraceStats};
+    use crate::warrant::trace_warrant::{TraceWarrant, WarrantError};
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub enum TraceEvent {
+        AncestorFound { edge: TraceEdge, stats: TraceStats },
+        // a sender without any predecessor
+        OriginReached { uid: Uid, stats: TraceStats },
+        ReceiverBatch { sid: Uid, edges: Vec<TraceEdge>, stats: TraceStats },
+        // the frontier is left empty when the scope hides the chain
+        RoundComplete { round: usize, frontier: Vec<Uid>, frontier_len: usize, stats: TraceStats },
+    }
+
+    // yields the events of `tracing` as they happen, backward search of a round comes out before its forward search
+    pub struct TraceStream<'a> {
+        report: &'a MsgReport,
+        warrant: &'a TraceWarrant<'a>,
+        state: TraceState,
+        pending: VecDeque<TraceEvent>,
+        // backward search of the current round done, forward search not yet
+        mid_round: bool,
+        audited: bool,
+    }
+
+    impl<'a> TraceStream<'a> {
+        pub fn new(report: &'a MsgReport, snd_start: &Uid, warrant: &'a TraceWarrant<'a>) -> Result<Self, WarrantError> {
+            TraceStream::resume(report, TraceState::new(report, snd_start, warrant)?, warrant)
+        }
+
+        pub fn resume(report: &'a MsgReport, mut state: TraceState, warrant: &'a TraceWarrant<'a>) -> Result<Self, WarrantError> {
+            state.resume(report, warrant)?;
+            Ok(TraceStream { report, warrant, state, pending: VecDeque::new(), mid_round: false, audited: false })
+        }
+
+        pub fn state(&self) -> &TraceState {
+            &self.state
+        }
+
+        // stop early, finishing the round in progress so the state can be resumed
+        pub fn into_state(mut self) -> TraceState {
+            if self.mid_round {
+                self.state.forward_phase(self.report, self.warrant, &TraceBudget::unlimited(), &mut |_| {});
             }
-            // pop the receivers that already search
-            let mut prev_rcv_set = rcv_set;
-            rcv_set = prev_rcv_set.split_off(rcv_len_at_begin);
-            for user in prev_rcv_set {
-                searched_rcv.insert(user.hash());
+            self.state
+        }
+    }
+
+    impl Iterator for TraceStream<'_> {
+        type Item = TraceEvent;
+
+        fn next(&mut self) -> Option<TraceEvent> {
+            loop {
+                if let Some(event) = self.pending.pop_front() {
+                    return Some(event);
+                }
+                // the state can be taken out and resumed once the log is back
+                if self.state.is_halted() {
+                    return None;
+                }
+                let pending = &mut self.pending;
+                match (self.mid_round, self.state.is_done()) {
+                    (false, true) => {
+                        // logged once however often the exhausted stream is polled, tried again while it fails
+                        if !self.audited {
+                            self.audited = self.state.audit_finished().is_ok();
+                        }
+                        return None;
+                    },
+                    (false, false) => self.state.backward_phase(self.report, self.warrant, &TraceBudget::unlimited(), &mut |event| pending.push_back(event)),
+                    (true, _) => self.state.forward_phase(self.report, self.warrant, &TraceBudget::unlimited(), &mut |event| pending.push_back(event)),
+                }
+                self.mid_round = !self.mid_round;
             }
         }
-        path
     }
 }
 
@@ -240,17 +1196,29 @@ pub mod tests {
     extern crate base64;
     extern crate test;
 
-    use std::{collections::HashMap, vec};
+    use std::{collections::{HashMap, HashSet}, time::Duration, vec};
                 
     use base64::encode;
     use rand;
     
-    use crate::{db::{db_tag, db_ik, db_nbr}, message::messaging::{self, IdKey}, tool::algos::tk_gen};
-    use crate::trace::traceback;
-    use crate::message::messaging::{MsgPacket, Edge, MsgReport};
+    use crate::{db::{db_tag, db_ik, db_nbr, db_grp, db_dev}, message::messaging::{self, IdKey}, tool::algos::{tk_gen, group_tk_gen, proc_tag_gen}};
+    use crate::trace::traceback::{self, TraceLimits, TraceState};
+    use crate::trace::trace_result::{TraceResult, TraceEdge, TraceStats, SearchDir};
+    use crate::trace::trace_job::TraceJob;
+    use crate::trace::trace_stream::{TraceStream, TraceEvent};
+    use crate::trace::trace_scope::{TraceScope, ScopePolicy};
+    use crate::warrant::trace_warrant::{ModeratorSet, ModeratorShare, WarrantRequest, WarrantError, TraceWarrant};
+    use crate::audit::audit_log::{self, AuditEvent};
+    use crate::message::messaging::{MsgPacket, Edge, MsgReport, Uid};
+    use crate::message::sealed_sender::{TraceAuthority, send_sealed_packet, plt_proc_sealed_packet, submit_sealed_report, ta_verify_report};
     
     const OURS_BRANCH: u32 = 10;
 
+    // the identity keys of the tests are sealed to this 2-of-3 set
+    lazy_static::lazy_static! {
+        static ref MODERATORS: (ModeratorSet, Vec<ModeratorShare>) = ModeratorSet::setup(2, &uids(&[101, 102, 103]), ScopePolicy::all());
+    }
+
     #[test]
     fn test_tracing() {
         // Path case 2: 1-2-3-4-5, 3-6-7, 6-8
@@ -259,7 +1227,8 @@ pub mod tests {
         let report_key = keys.get(start_index).unwrap();
 
         // Search this message from middle node
-        let fwd_graph = traceback::tracing(&MsgReport {key: *report_key, payload: message}, users.get(start_index + 1).unwrap());
+        let report = MsgReport {key: *report_key, payload: message};
+        let fwd_graph = traceback::tracing(&report, users.get(start_index + 1).unwrap(), &warrant(&report)).unwrap();
         assert_eq!(fwd_graph.is_empty(), false);
 
         fwd_graph.into_iter().for_each(|e| {
@@ -269,66 +1238,650 @@ pub mod tests {
         db_clear();
     }
     
+    #[test]
+    fn test_tracing_multi_part() {
+        // 1 sends [text, image] to 2; 2 forwards the image alone to 3; 3 bundles it with new text to 4
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let (text, image) = ("text".to_string(), "image".to_string());
+
+        let tk_12 = tk_gen(map_id_ik.get(&Uid(1)).unwrap(), &Uid(2));
+        let bundle_12 = messaging::send_multi_packet(&[(text.clone(), [0; 16]), (image.clone(), [0; 16])], &tk_12);
+        messaging::store_multi_tag(&bundle_12);
+
+        let tk_23 = tk_gen(map_id_ik.get(&Uid(2)).unwrap(), &Uid(3));
+        let packet_23 = messaging::forward_part(&bundle_12, 1, &tk_23).unwrap();
+        let _ = db_tag::add(&vec![encode(packet_23.p_tag)]);
+
+        let tk_34 = tk_gen(map_id_ik.get(&Uid(3)).unwrap(), &Uid(4));
+        let bundle_34 = messaging::send_multi_packet(&[("other text".to_string(), [0; 16]), (image.clone(), packet_23.tag_key)], &tk_34);
+        messaging::store_multi_tag(&bundle_34);
+
+        let (report, sess) = messaging::submit_part_report(&bundle_34, 1, &Edge::new(&Uid(3), &Uid(4))).unwrap();
+        let path = traceback::tracing(&report, &sess.rid, &warrant(&report)).unwrap();
+        let mut edges: Vec<(Uid, Uid)> = path.iter().map(|e| (e.sid, e.rid)).collect();
+        edges.sort();
+        edges.dedup();
+        assert_eq!(edges, vec![(Uid(1), Uid(2)), (Uid(2), Uid(3)), (Uid(3), Uid(4))]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_group() {
+        // 1 sends to 2; 2 sends to group 100 = {2, 3, 4} at 20; 4 forwards to 6
+        // 5 left the group before the send and 7 joined after it
+        let gid = Uid(100);
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5, 6, 7, 100]);
+        let map_id_ik = register_users(&users);
+        let _ = db_nbr::add(&vec![Edge::new(&Uid(1), &Uid(2)), Edge::new(&Uid(4), &Uid(6))]);
+        let _ = db_grp::add_members(&gid, &uids(&[2, 3, 4, 5]), &10);
+        let _ = db_grp::remove_members(&gid, &uids(&[5]), &15);
+        let _ = db_grp::add_members(&gid, &uids(&[7]), &30);
+        let message = "message".to_string();
+
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let grp_tk = group_tk_gen(map_id_ik.get(&gid).unwrap(), &Uid(2));
+        let mut grp_packet = messaging::send_packet(&message, &first_packet.tag_key, &grp_tk);
+        let _ = messaging::open_group_session(&Uid(2), &gid, map_id_ik.get(&gid).unwrap());
+        assert!(messaging::plt_proc_group_packet(&Uid(2), &gid, &mut grp_packet));
+        messaging::store_group_tag(&grp_packet, &20);
+        let packet = fwd_edge_gen(&message, &Uid(4), &Uid(6), &grp_packet, &map_id_ik);
+        let _ = db_tag::add(&vec![encode(packet.p_tag)]);
+
+        let report = MsgReport {key: packet.tag_key, payload: message};
+        let path = traceback::tracing(&report, &Uid(6), &warrant(&report)).unwrap();
+        let mut edges: Vec<(Uid, Uid, Option<Uid>)> = path.iter().map(|e| (e.sid, e.rid, e.gid)).collect();
+        edges.sort();
+        edges.dedup();
+        assert_eq!(edges, vec![(Uid(1), Uid(2), None), (Uid(2), Uid(3), Some(gid)), (Uid(2), Uid(4), Some(gid)), (Uid(4), Uid(6), None)]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_multi_device() {
+        // user 1 = {11, 12}, user 2 = {21, 22}, user 3 = {31, 32}
+        // 11 sends to user 2, 21 forwards to user 3, and 31 forwards to its own device 32
+        let map_uid_devs: HashMap<Uid, Vec<Uid>> = HashMap::from([(Uid(1), uids(&[11, 12])), (Uid(2), uids(&[21, 22])), (Uid(3), uids(&[31, 32]))]);
+        let mut map_id_ik: HashMap<Uid, [u8; 16]> = HashMap::new();
+        for (uid, devs) in &map_uid_devs {
+            let dev_keys: Vec<IdKey> = devs.iter().map(|did| IdKey::id_as_key_gen(*did)).collect();
+            dev_keys.iter().for_each(|dk| { map_id_ik.insert(dk.id, dk.key); });
+            let _ = db_dev::register(uid, &dev_keys, &MODERATORS.0.public_key());
+        }
+        let _ = db_nbr::add_device_sessions(&[Edge::new(&Uid(1), &Uid(2)), Edge::new(&Uid(2), &Uid(3))]);
+        let message = "message".to_string();
+
+        let packets_1 = messaging::send_packet_to_devices(&message, &[0; 16], map_id_ik.get(&Uid(11)).unwrap(), &map_uid_devs[&Uid(2)]);
+        let packet_21 = &packets_1.iter().find(|(did, _)| *did == Uid(21)).unwrap().1;
+        let packets_2 = messaging::send_packet_to_devices(&message, &packet_21.tag_key, map_id_ik.get(&Uid(21)).unwrap(), &map_uid_devs[&Uid(3)]);
+        let packet_31 = &packets_2.iter().find(|(did, _)| *did == Uid(31)).unwrap().1;
+        let packet_32 = fwd_edge_gen(&message, &Uid(31), &Uid(32), packet_31, &map_id_ik);
+        let tags: Vec<String> = packets_1.iter().chain(packets_2.iter()).map(|(_, p)| encode(p.p_tag)).chain([encode(packet_32.p_tag)]).collect();
+        let _ = db_tag::add(&tags);
+
+        let report = MsgReport {key: packet_32.tag_key, payload: message};
+        let (user_path, dev_path) = traceback::tracing_by_user(&report, &Uid(32), &warrant(&report)).unwrap();
+        let mut user_edges: Vec<(Uid, Uid)> = user_path.iter().map(|e| (e.sid, e.rid)).collect();
+        user_edges.sort();
+        assert_eq!(user_edges, vec![(Uid(1), Uid(2)), (Uid(2), Uid(3))]);
+        let mut dev_edges: Vec<(Uid, Uid)> = dev_path.iter().map(|e| (e.sid, e.rid)).collect();
+        dev_edges.sort();
+        dev_edges.dedup();
+        assert_eq!(dev_edges, vec![(Uid(11), Uid(21)), (Uid(11), Uid(22)), (Uid(21), Uid(31)), (Uid(21), Uid(32)), (Uid(31), Uid(32))]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_sealed_sender() {
+        // 1 -> 2 -> 3 in sealed sender mode, reported by 3
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+
+        let _ = messaging::open_session(&Edge::new(&Uid(1), &Uid(2)), map_id_ik.get(&Uid(1)).unwrap());
+        let _ = messaging::open_session(&Edge::new(&Uid(2), &Uid(3)), map_id_ik.get(&Uid(2)).unwrap());
+        let tk_12 = tk_gen(map_id_ik.get(&Uid(1)).unwrap(), &Uid(2));
+        let packet_12 = send_sealed_packet(&message, &[0; 16], &tk_12, &Uid(1), &ta.public);
+        assert!(plt_proc_sealed_packet(&ta, &packet_12));
+        let tk_23 = tk_gen(map_id_ik.get(&Uid(2)).unwrap(), &Uid(3));
+        let packet_23 = send_sealed_packet(&message, &packet_12.packet.tag_key, &tk_23, &Uid(2), &ta.public);
+        assert!(plt_proc_sealed_packet(&ta, &packet_23));
+
+        let (report, sealed) = submit_sealed_report(&packet_23.packet.tag_key, &message, &packet_23);
+        assert_eq!(ta_verify_report(&ta, &Uid(3), &report, &sealed), Some(Uid(2)));
+        assert_eq!(ta_verify_report(&ta, &Uid(4), &report, &sealed), None);
+
+        let path = traceback::tracing(&report, &Uid(3), &warrant(&report)).unwrap();
+        let mut edges: Vec<(Uid, Uid)> = path.iter().map(|e| (e.sid, e.rid)).collect();
+        edges.sort();
+        edges.dedup();
+        assert_eq!(edges, vec![(Uid(1), Uid(2)), (Uid(2), Uid(3))]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_ambiguous_parent() {
+        // 1-2-3, plus a stored tag that makes 4 look like another sender of 3
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3]), &map_id_ik);
+        let fake_tk = tk_gen(map_id_ik.get(&Uid(4)).unwrap(), &Uid(3));
+        let _ = db_tag::add(&vec![encode(proc_tag_gen(&keys[1], &fake_tk, &message))]);
+
+        let report = MsgReport {key: keys[1], payload: message};
+        let (path, ambiguity) = traceback::tracing_with_ambiguity(&report, &Uid(3), &warrant(&report)).unwrap();
+        let mut edges: Vec<(Uid, Uid)> = path.iter().map(|e| (e.sid, e.rid)).collect();
+        edges.sort();
+        edges.dedup();
+        assert_eq!(edges, vec![(Uid(1), Uid(2)), (Uid(2), Uid(3)), (Uid(4), Uid(3))]);
+        assert_eq!(ambiguity.len(), 1);
+        let mut candidates: Vec<Uid> = ambiguity[0].candidates.iter().map(|td| td.uid).collect();
+        candidates.sort();
+        assert_eq!((ambiguity[0].uid, candidates), (Uid(3), uids(&[2, 4])));
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_result() {
+        // 1-2-3-4, reported by 3
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4]), &map_id_ik);
+
+        let report = MsgReport {key: keys[1], payload: message};
+        let result = traceback::tracing_result(&report, &Uid(3), &warrant(&report), &ta.public).unwrap();
+        assert_eq!(result.origin(), Some(Uid(1)));
+        let mut edges: Vec<(Uid, Uid, usize, SearchDir)> = result.edges.iter().map(|e| (e.sid, e.rid, e.depth, e.dir)).collect();
+        edges.sort_by_key(|e| (e.0, e.1));
+        edges.dedup();
+        assert_eq!(edges, vec![(Uid(1), Uid(2), 1, SearchDir::Backward), (Uid(2), Uid(3), 2, SearchDir::Backward), (Uid(3), Uid(4), 3, SearchDir::Forward)]);
+        assert!(result.stats.tag_lookups > 0);
+        let reporter_key = result.node_keys.iter().find(|nk| nk.uid == Uid(3)).unwrap();
+        assert_eq!(ta.open_key(&reporter_key.key), Some(keys[1]));
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_origin_zero() {
+        // 0-1-2 reported by 2, the origin 0 is a user like any other
+        let users: Vec<Uid> = uids(&[0, 1, 2]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(0), &Uid(1));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[1, 2]), &map_id_ik);
+
+        let report = MsgReport {key: keys[1], payload: message};
+        let result = traceback::tracing_result(&report, &Uid(2), &warrant(&report), &ta.public).unwrap();
+        assert_eq!(result.origin(), Some(Uid(0)));
+        let mut edges: Vec<(Uid, Uid)> = result.edges.iter().map(|e| (e.sid, e.rid)).collect();
+        edges.sort();
+        assert_eq!(edges, vec![(Uid(0), Uid(1)), (Uid(1), Uid(2))]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_limited() {
+        // 1-2-3-4-5 reported by 5, stopped after one round and resumed
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4, 5]), &map_id_ik);
+        let report = MsgReport {key: keys[3], payload: message};
+
+        let warrant = warrant(&report);
+        let limits = TraceLimits { max_depth: Some(1), ..TraceLimits::unlimited() };
+        let (partial, sealed) = traceback::tracing_limited(&report, &Uid(5), &warrant, &limits, &ta.public).unwrap();
+        assert!(!partial.complete);
+        assert_eq!((partial.stats.rounds, partial.frontier.clone(), partial.origin()), (1, uids(&[4]), None));
+        assert!(sealed.open(&TraceAuthority::new()).is_none());
+
+        // the visited limit is hit by the backward search of the first round, which leaves 5 unsearched forward
+        let limits = TraceLimits { max_visited: Some(1), ..TraceLimits::unlimited() };
+        let (cut, _) = traceback::tracing_limited(&report, &Uid(5), &warrant, &limits, &ta.public).unwrap();
+        assert_eq!((cut.stats.rounds, cut.stats.visited_users, cut.frontier), (1, 1, uids(&[4, 5])));
+
+        let (resumed, _) = traceback::resume_tracing(&report, sealed.open(&ta).unwrap(), &warrant, &TraceLimits::unlimited(), &ta.public).unwrap();
+        let full = traceback::tracing_result(&report, &Uid(5), &warrant, &ta.public).unwrap();
+        assert!(resumed.complete & resumed.frontier.is_empty());
+        assert_eq!(resumed.origin(), Some(Uid(1)));
+        let edges = |r: &TraceResult| {
+            let mut edges: Vec<(Uid, Uid, usize)> = r.edges.iter().map(|e| (e.sid, e.rid, e.depth)).collect();
+            edges.sort();
+            edges
+        };
+        assert_eq!(edges(&resumed), edges(&full));
+
+        // a warrant for another scope does not resume the state, nor does a state edited to search forward
+        let origin_only = scoped_warrant(&report, TraceScope::OriginOnly, "moderator");
+        let error = traceback::resume_tracing(&report, sealed.open(&ta).unwrap(), &origin_only, &TraceLimits::unlimited(), &ta.public).unwrap_err();
+        assert_eq!(error, WarrantError::ScopeNotPermitted);
+        let limits = TraceLimits { max_depth: Some(1), ..TraceLimits::unlimited() };
+        let (_, sealed) = traceback::tracing_limited(&report, &Uid(5), &origin_only, &limits, &ta.public).unwrap();
+        let mut edited = serde_json::to_value(sealed.open(&ta).unwrap()).unwrap();
+        edited["forward"] = true.into();
+        let edited: TraceState = serde_json::from_value(edited).unwrap();
+        let error = traceback::resume_tracing(&report, edited, &origin_only, &TraceLimits::unlimited(), &ta.public).unwrap_err();
+        assert_eq!(error, WarrantError::ScopeNotPermitted);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_limited_batches() {
+        // 0 sends to 1, 1 forwards to 70 users, more than one forward batch, and each of them forwards to both 1000 and 1001
+        let senders: Vec<Uid> = (100..170).map(Uid).collect();
+        let receivers: Vec<Uid> = uids(&[1000, 1001]);
+        let users: Vec<Uid> = uids(&[0, 1]).into_iter().chain(senders.clone()).chain(receivers.clone()).collect();
+        let map_id_ik = register_users(&users);
+        let mut sessions = vec![Edge::new(&Uid(0), &Uid(1))];
+        for sid in &senders {
+            sessions.push(Edge::new(&Uid(1), sid));
+            sessions.extend(receivers.iter().map(|rid| Edge::new(sid, rid)));
+        }
+        let _ = db_nbr::add(&sessions);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(0), &Uid(1));
+        let mut tags: Vec<String> = Vec::new();
+        for sid in &senders {
+            let packet = fwd_edge_gen(&message, &Uid(1), sid, &first_packet, &map_id_ik);
+            tags.push(encode(packet.p_tag));
+            tags.extend(receivers.iter().map(|rid| encode(fwd_edge_gen(&message, sid, rid, &packet, &map_id_ik).p_tag)));
+        }
+        let _ = db_tag::add(&tags);
+        let report = MsgReport {key: first_packet.tag_key, payload: message};
+
+        // a limit that is never reached still searches forward in batches
+        let warrant = warrant(&report);
+        let limits = TraceLimits { max_depth: Some(1), ..TraceLimits::unlimited() };
+        let (_, sealed) = traceback::tracing_limited(&report, &Uid(1), &warrant, &limits, &ta.public).unwrap();
+        let limits = TraceLimits { timeout: Some(Duration::from_secs(3600)), ..TraceLimits::unlimited() };
+        let (resumed, _) = traceback::resume_tracing(&report, sealed.open(&ta).unwrap(), &warrant, &limits, &ta.public).unwrap();
+        let full = traceback::tracing_result(&report, &Uid(1), &warrant, &ta.public).unwrap();
+        let edges = |r: &TraceResult| {
+            let mut edges: Vec<(Uid, Uid, usize)> = r.edges.iter().map(|e| (e.sid, e.rid, e.depth)).collect();
+            edges.sort();
+            edges
+        };
+        assert!(resumed.complete);
+        assert_eq!(edges(&resumed), edges(&full));
+        assert_eq!(full.edges.iter().filter(|e| e.rid == Uid(1000)).count(), senders.len());
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_trace_job_resume() {
+        // 1-2-3-4-5 reported by 5, interrupted after two rounds and resumed from the checkpoint file
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4, 5]), &map_id_ik);
+        let path = std::env::temp_dir().join(format!("trace_job_{}.json", hex::encode(rand::random::<[u8; 8]>())));
+
+        let report = MsgReport {key: keys[3], payload: message.clone()};
+        let warrant = warrant(&report);
+        let mut job = TraceJob::new(MsgReport {key: keys[3], payload: message.clone()}, &Uid(5), &warrant).unwrap().with_checkpoint(&path, &ta.public);
+        job.step(&warrant).unwrap();
+        job.step(&warrant).unwrap();
+        drop(job);
+
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&message));
+        assert!(TraceJob::load(&path, &TraceAuthority::new()).is_err());
+        let mut resumed = TraceJob::load(&path, &ta).unwrap();
+        assert_eq!(resumed.state().stats().rounds, 2);
+        let other = MsgReport {key: keys[2], payload: message};
+        assert!(resumed.run(&self::warrant(&other), &TraceLimits::unlimited()).is_err());
+        resumed.run(&warrant, &TraceLimits::unlimited()).unwrap();
+        let full = traceback::tracing_result(&report, &Uid(5), &warrant, &ta.public).unwrap();
+        let result = resumed.result(&ta.public);
+        assert!(result.complete);
+        let edges = |r: &TraceResult| {
+            let mut edges: Vec<(Uid, Uid, usize)> = r.edges.iter().map(|e| (e.sid, e.rid, e.depth)).collect();
+            edges.sort();
+            edges
+        };
+        assert_eq!(edges(&result), edges(&full));
+        assert_eq!(TraceJob::load(&path, &ta).unwrap().state().stats().rounds, result.stats.rounds);
+
+        let _ = std::fs::remove_file(path);
+        db_clear();
+    }
+
+    #[test]
+    fn trace_job_checkpoint() {
+        let ta = TraceAuthority::new();
+        let report = MsgReport {key: [7; 16], payload: "message".to_string()};
+        let job = TraceJob::new(MsgReport {key: [7; 16], payload: "message".to_string()}, &Uid(3), &warrant(&report)).unwrap();
+        let checkpoint = job.checkpoint(&ta.public);
+        assert!(!checkpoint.contains("message"));
+        let restored = TraceJob::from_checkpoint(&checkpoint, &ta).unwrap();
+        assert_eq!(restored.report.key, [7; 16]);
+        assert_eq!(restored.state().frontier(), uids(&[3]));
+        assert!(!restored.is_done());
+    }
+
+    #[test]
+    fn test_trace_stream() {
+        // 1-2-3-4-5 reported by 4, stopped as soon as the origin is reached
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4, 5]), &map_id_ik);
+        let report = MsgReport {key: keys[2], payload: message};
+
+        let warrant = warrant(&report);
+        let mut stream = TraceStream::new(&report, &Uid(4), &warrant).unwrap();
+        let mut ancestors: Vec<(Uid, Uid)> = Vec::new();
+        let origin = stream.by_ref().find_map(|event| match event {
+            TraceEvent::AncestorFound { edge, .. } => {
+                ancestors.push((edge.sid, edge.rid));
+                None
+            },
+            TraceEvent::OriginReached { uid, .. } => Some(uid),
+            _ => None,
+        });
+        assert_eq!(origin, Some(Uid(1)));
+        assert_eq!(ancestors, vec![(Uid(3), Uid(4)), (Uid(2), Uid(3)), (Uid(1), Uid(2))]);
+
+        let (resumed, _) = traceback::resume_tracing(&report, stream.into_state(), &warrant, &TraceLimits::unlimited(), &ta.public).unwrap();
+        let events: Vec<TraceEvent> = TraceStream::new(&report, &Uid(4), &warrant).unwrap().collect();
+        let full = traceback::tracing_result(&report, &Uid(4), &warrant, &ta.public).unwrap();
+        let rounds = events.iter().filter(|event| matches!(event, TraceEvent::RoundComplete { .. })).count();
+        assert_eq!((rounds, resumed.stats.rounds), (full.stats.rounds, full.stats.rounds));
+        let mut streamed: Vec<(Uid, Uid)> = events.iter().flat_map(|event| match event {
+            TraceEvent::AncestorFound { edge, .. } => vec![(edge.sid, edge.rid)],
+            TraceEvent::ReceiverBatch { edges, .. } => edges.iter().map(|e| (e.sid, e.rid)).collect(),
+            _ => Vec::new(),
+        }).collect();
+        let mut traced: Vec<(Uid, Uid)> = full.edges.iter().map(|e| (e.sid, e.rid)).collect();
+        streamed.sort();
+        traced.sort();
+        assert_eq!(streamed, traced);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_scoped() {
+        // 1-2-3-4-5 reported by 4
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4, 5]), &map_id_ik);
+        let report = MsgReport {key: keys[2], payload: message};
+        let scoped = |scope: TraceScope| {
+            let result = traceback::tracing_result(&report, &Uid(4), &scoped_warrant(&report, scope, "moderator"), &ta.public).unwrap();
+            let mut edges: Vec<(Uid, Uid, usize, SearchDir)> = result.edges.iter().map(|e| (e.sid, e.rid, e.depth, e.dir)).collect();
+            edges.sort_by_key(|e| (e.0, e.1));
+            (result, edges)
+        };
+
+        let (origin_only, edges) = scoped(TraceScope::OriginOnly);
+        assert_eq!((origin_only.origin(), edges), (Some(Uid(1)), Vec::new()));
+        assert_eq!(origin_only.node_keys.iter().map(|nk| nk.uid).collect::<Vec<Uid>>(), uids(&[1]));
+
+        let (path, edges) = scoped(TraceScope::PathToOrigin);
+        assert_eq!(path.origin(), Some(Uid(1)));
+        assert_eq!(edges, vec![(Uid(1), Uid(2), 1, SearchDir::Backward), (Uid(2), Uid(3), 2, SearchDir::Backward), (Uid(3), Uid(4), 3, SearchDir::Backward)]);
+
+        let (subtree, edges) = scoped(TraceScope::ForwardSubtree(Uid(3)));
+        assert_eq!(subtree.origin(), None);
+        assert_eq!(edges, vec![(Uid(3), Uid(4), 1, SearchDir::Forward), (Uid(4), Uid(5), 2, SearchDir::Forward)]);
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_origin_only_hidden() {
+        // 1-2-3-4-5 reported by 4, an origin-only trace hands out no user but 4 and the origin 1
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4, 5]), &map_id_ik);
+        let report = MsgReport {key: keys[2], payload: message};
+        let warrant = scoped_warrant(&report, TraceScope::OriginOnly, "moderator");
+        let allowed: HashSet<Uid> = uids(&[1, 4]).into_iter().collect();
+        let result_uids = |r: &TraceResult| -> HashSet<Uid> {
+            [r.reporter].into_iter()
+                .chain(r.origins.clone())
+                .chain(r.edges.iter().flat_map(|e| [e.sid, e.rid]))
+                .chain(r.node_keys.iter().map(|nk| nk.uid))
+                .chain(r.ambiguous_hops.iter().flat_map(|hop| [hop.uid].into_iter().chain(hop.candidates.clone())))
+                .chain(r.frontier.clone())
+                .collect()
+        };
+
+        let limits = TraceLimits { max_depth: Some(1), ..TraceLimits::unlimited() };
+        let (partial, sealed) = traceback::tracing_limited(&report, &Uid(4), &warrant, &limits, &ta.public).unwrap();
+        assert!(!partial.complete & partial.frontier.is_empty());
+        assert_eq!(partial.frontier_len, 1);
+        assert!(result_uids(&partial).is_subset(&allowed));
+        let (resumed, _) = traceback::resume_tracing(&report, sealed.open(&ta).unwrap(), &warrant, &TraceLimits::unlimited(), &ta.public).unwrap();
+        assert!(resumed.complete);
+        assert_eq!(result_uids(&resumed), allowed);
+        assert_eq!(resumed.stats.visited_users, 4);
+
+        let mut stream = TraceStream::new(&report, &Uid(4), &warrant).unwrap();
+        let mut streamed: HashSet<Uid> = HashSet::new();
+        for event in stream.by_ref() {
+            match event {
+                TraceEvent::AncestorFound { edge, .. } => streamed.extend([edge.sid, edge.rid]),
+                TraceEvent::OriginReached { uid, .. } => { streamed.insert(uid); },
+                TraceEvent::ReceiverBatch { sid, edges, .. } => streamed.extend([sid].into_iter().chain(edges.iter().flat_map(|e| [e.sid, e.rid]))),
+                TraceEvent::RoundComplete { frontier, .. } => streamed.extend(frontier),
+            }
+        }
+        assert_eq!(streamed, uids(&[1]).into_iter().collect());
+        assert!(stream.state().frontier().is_empty());
+
+        db_clear();
+    }
+
+    #[test]
+    fn trace_scope_policy() {
+        let policy = ScopePolicy::origin_only();
+        assert!(policy.authorize(TraceScope::OriginOnly, "moderator").is_some());
+        assert!(policy.authorize(TraceScope::PathToOrigin, "moderator").is_none());
+        assert!(policy.authorize(TraceScope::ForwardSubtree(Uid(3)), "moderator").is_none());
+        assert!(policy.authorize(TraceScope::FullSpread, "moderator").is_none());
+        let policy = ScopePolicy { forward_subtree: true, ..ScopePolicy::origin_only() };
+        assert_eq!(policy.authorize(TraceScope::ForwardSubtree(Uid(3)), "moderator").unwrap().scope(), TraceScope::ForwardSubtree(Uid(3)));
+    }
+
+    #[test]
+    fn test_tracing_warranted() {
+        // 1-2-3-4 reported by 3, where db_ik only holds the keys sealed to the moderators
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3, 4]), &map_id_ik);
+        let report = MsgReport {key: keys[1], payload: message.clone()};
+        let warrant = warrant(&report);
+
+        let other = MsgReport {key: keys[2], payload: message};
+        assert_eq!(traceback::tracing_result(&other, &Uid(4), &warrant, &ta.public).unwrap_err(), WarrantError::NotCovered);
+        // a set the keys were not sealed to opens none of them
+        let (other_set, other_shares) = ModeratorSet::setup(1, &[Uid(104)], ScopePolicy::all());
+        let mut request = WarrantRequest::new(&other_set, &report, TraceScope::FullSpread, "moderator");
+        request.approve(&other_shares[0]).unwrap();
+        let result = traceback::tracing_result(&report, &Uid(3), &request.issue().unwrap(), &ta.public).unwrap();
+        assert!(result.edges.is_empty());
+
+        let result = traceback::tracing_result(&report, &Uid(3), &warrant, &ta.public).unwrap();
+        let mut edges: Vec<(Uid, Uid)> = result.edges.iter().map(|e| (e.sid, e.rid)).collect();
+        edges.sort();
+        edges.dedup();
+        assert_eq!((result.origin(), edges), (Some(Uid(1)), vec![(Uid(1), Uid(2)), (Uid(2), Uid(3)), (Uid(3), Uid(4))]));
+
+        db_clear();
+    }
+
+    #[test]
+    fn test_tracing_audited() {
+        // 1-2-3 reported by 3, traced path to origin by one operator
+        let users: Vec<Uid> = uids(&[1, 2, 3]);
+        let _ = db_nbr::add(&mock_nbr_full_connect(&users));
+        let map_id_ik = register_users(&users);
+        let ta = TraceAuthority::new();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
+        let keys = fwd_path_gen(&first_packet.tag_key, &message, &uids(&[2, 3]), &map_id_ik);
+        let report = MsgReport {key: keys[1], payload: message};
+
+        let warrant = scoped_warrant(&report, TraceScope::PathToOrigin, "alice");
+        let (_, sealed) = traceback::tracing_limited(&report, &Uid(3), &warrant, &TraceLimits::unlimited(), &ta.public).unwrap();
+        let state = sealed.open(&ta).unwrap();
+        let entries = audit_log::load();
+        assert_eq!(audit_log::verify(&entries, None), Ok(()));
+        let events: Vec<&AuditEvent> = entries.iter().map(|e| &e.event).filter(|event| match event {
+            AuditEvent::TraceStarted { operator, .. } | AuditEvent::TagQuery { operator, .. } | AuditEvent::TraceFinished { operator, .. } => operator == "alice",
+        }).collect();
+        assert!(matches!(events[0], AuditEvent::TraceStarted { scope: TraceScope::PathToOrigin, .. }));
+        // one backward query for each of 3, 2 and the origin 1
+        assert_eq!(events.iter().filter(|event| matches!(event, AuditEvent::TagQuery { .. })).count(), 3);
+        assert!(matches!(events.last().unwrap(), AuditEvent::TraceFinished { complete: true, result_digest, .. } if *result_digest == state.digest()));
+        // the trace keeps logging for alice, so it is not resumed under a warrant for anyone else
+        let other = scoped_warrant(&report, TraceScope::PathToOrigin, "bob");
+        assert_eq!(traceback::resume_tracing(&report, state, &other, &TraceLimits::unlimited(), &ta.public).unwrap_err(), WarrantError::ScopeNotPermitted);
+
+        db_clear();
+    }
+
+    #[test]
+    fn trace_result_export() {
+        let ta = TraceAuthority::new();
+        let edges = vec![
+            TraceEdge::new(&Uid(1), &Uid(2), &None, SearchDir::Backward),
+            TraceEdge::new(&Uid(2), &Uid(3), &Some(Uid(9)), SearchDir::Forward),
+        ];
+        let result = TraceResult::new(&Uid(2), uids(&[1]), edges, &[], &[], TraceStats::default(), &ta.public);
+        assert_eq!(result.edges.iter().map(|e| e.depth).collect::<Vec<usize>>(), vec![1, 2]);
+        assert_eq!(result.to_edges()[1].gid, Some(Uid(9)));
+
+        let dot = result.to_dot();
+        assert!(dot.contains("U1 (origin)") & dot.contains("U2 (reporter)") & dot.contains("2 fwd G9"));
+        let json: TraceResult = serde_json::from_str(&result.to_json()).unwrap();
+        assert_eq!((json.reporter, json.origins, json.edges.len()), (Uid(2), uids(&[1]), 2));
+    }
+
     #[test]
     fn trace_tree () {
         let branch: u32 = 3;
         let depth: u32 = 8;
         let tree_size = calc_tree_size(&depth, &branch);
-        let vec_user = (0..tree_size + 1).collect::<Vec<u32>>();
+        let vec_user = (0..tree_size + 1).map(Uid).collect::<Vec<Uid>>();
         let map_id_ik = register_users(&vec_user);
 
         let origin_id = vec_user.get(tree_size as usize).unwrap();
-        let root_id = vec_user.get(0).unwrap();
-        let first_packet = new_edge_gen(&"message".to_string(), origin_id, &root_id);
+        let root_id = vec_user.first().unwrap();
+        let message = "message".to_string();
+        let first_packet = new_edge_gen(&message, origin_id, root_id);
         let mut vec_tag = Vec::<String>::new();
         let mut vec_edge = Vec::<Edge>::new();
 
-        mock_tree_recursive(&root_id, &first_packet, &branch, &1, &depth, &"message".to_string(), &map_id_ik, &mut vec_tag, &mut vec_edge);
+        let tree = MockTree {branch, depth, message: &message, map_id_ik: &map_id_ik};
+        mock_tree_recursive(&tree, root_id, &first_packet, 1, &mut vec_tag, &mut vec_edge);
         let _ = db_tag::add(&vec_tag);
         let _ = db_nbr::add(&vec_edge);
 
-        let path =  traceback::tracing(&MsgReport {key: first_packet.tag_key, payload: "message".to_string()}, &root_id);
+        let report = MsgReport {key: first_packet.tag_key, payload: "message".to_string()};
+        let path =  traceback::tracing(&report, root_id, &warrant(&report)).unwrap();
 
         // println!("Path-Tree: {}-{}", path.len(), tree_size - 1);
 
-        assert_eq!(tree_size -1, path.len() as u32);
+        assert_eq!(tree_size -1, path.len() as u128);
 
         db_clear();
     }
 
-    fn register_users (vec_uid: &Vec<u32>) -> HashMap<u32, [u8; 16]> {
+    fn uids(ids: &[u128]) -> Vec<Uid> {
+        ids.iter().map(|id| Uid(*id)).collect()
+    }
+
+    fn warrant(report: &MsgReport) -> TraceWarrant<'static> {
+        scoped_warrant(report, TraceScope::FullSpread, "moderator")
+    }
+
+    // approved by two of the three moderators
+    fn scoped_warrant(report: &MsgReport, scope: TraceScope, operator: &str) -> TraceWarrant<'static> {
+        let (set, shares) = &*MODERATORS;
+        let mut request = WarrantRequest::new(set, report, scope, operator);
+        request.approve(&shares[0]).unwrap();
+        request.approve(&shares[1]).unwrap();
+        request.issue().unwrap()
+    }
+
+    fn register_users (vec_uid: &[Uid]) -> HashMap<Uid, [u8; 16]> {
         let mut vec_id_key = Vec::<IdKey>::new();
         for uid in vec_uid {
             // let id_key = IdKey::rand_key_gen(*uid);
             let id_key = IdKey::id_as_key_gen(*uid);
             vec_id_key.push(id_key);
         }
-        let _ = db_ik::add(&vec_id_key);
+        let _ = db_ik::add(&vec_id_key, &MODERATORS.0.public_key());
         // convert vec_id_key to hmap
-        let map_id_key: HashMap<u32, [u8; 16]> = vec_id_key.into_iter().map(|id_key| (id_key.id, id_key.key)).collect();
+        let map_id_key: HashMap<Uid, [u8; 16]> = vec_id_key.into_iter().map(|id_key| (id_key.id, id_key.key)).collect();
         map_id_key
     }
 
     // Create a forwarding tree: 1-2-3-4-5, 3-6-7, 6-8
-    fn create_path_case() -> (Vec<u32>, Vec<[u8;16]>, String) {
-        let users: Vec<u32> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    fn create_path_case() -> (Vec<Uid>, Vec<[u8;16]>, String) {
+        let users: Vec<Uid> = uids(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
         let message = "message".to_string();
         let mut sess = mock_nbr_full_connect(&users);
 
         let _ = db_nbr::add(&mut sess);
         let map_id_ik = register_users(&users);
         // Path 0: 1-2-3-4-5
-        let first_packet = new_edge_gen(&message, &1, &2);
+        let first_packet = new_edge_gen(&message, &Uid(1), &Uid(2));
 
-        let path_1: Vec<u32> = vec![2, 3, 4, 5, 9, 8];
+        let path_1: Vec<Uid> = uids(&[2, 3, 4, 5, 9, 8]);
         let mut keys_1: Vec<[u8; 16]> = fwd_path_gen(&first_packet.tag_key, &message, &path_1, &map_id_ik); 
 
         // Path 2: 3-6-7
-        let path_2: Vec<u32> = vec![3, 6, 7];
+        let path_2: Vec<Uid> = uids(&[3, 6, 7]);
         let mut keys_2 = fwd_path_gen(keys_1.get(1).unwrap(), &message, &path_2, &map_id_ik);
 
         // Path 3: 6-8
-        let path_3: Vec<u32> = vec![6, 8];
+        let path_3: Vec<Uid> = uids(&[6, 8]);
         let mut keys_3 = fwd_path_gen(keys_2.get(1).unwrap(), &message, &path_3, &map_id_ik);
 
         keys_1.append(&mut keys_2.split_off(1));
@@ -343,42 +1896,48 @@ pub mod tests {
         db_tag::clear();
     }
 
-    fn mock_tree_recursive(root: &u32, prev_packet: &MsgPacket, branch: &u32, curr_depth: &u32, depth: &u32, message: &String, map_id_ik: &HashMap<u32, [u8;16]>, vec_tag: &mut Vec<String>, vec_edge: &mut Vec<Edge>) {
-        if curr_depth < depth {
-            for i in 0..*branch {
-                let rid = root * branch + i + 1;
+    // the shape of a mocked forwarding tree and the keys its users forward with
+    struct MockTree<'a> {
+        branch: u32,
+        depth: u32,
+        message: &'a String,
+        map_id_ik: &'a HashMap<Uid, [u8;16]>,
+    }
+
+    fn mock_tree_recursive(tree: &MockTree, root: &Uid, prev_packet: &MsgPacket, curr_depth: u32, vec_tag: &mut Vec<String>, vec_edge: &mut Vec<Edge>) {
+        if curr_depth < tree.depth {
+            for i in 0..tree.branch {
+                let rid = Uid(root.0 * (tree.branch as u128) + (i as u128) + 1);
                 vec_edge.push(Edge::new(root, &rid));
-                let packet = fwd_edge_gen(message, root, &rid, prev_packet, map_id_ik);
+                let packet = fwd_edge_gen(tree.message, root, &rid, prev_packet, tree.map_id_ik);
                 vec_tag.push(encode(packet.p_tag));
-                mock_tree_recursive(&rid, &packet, branch, &(curr_depth + 1), depth, message, map_id_ik, vec_tag, vec_edge);
+                mock_tree_recursive(tree, &rid, &packet, curr_depth + 1, vec_tag, vec_edge);
             }
         }
     }
 
-    fn calc_tree_size(depth: &u32, branch: &u32) -> u32 {
-        let mut size: u32 = 0;
+    fn calc_tree_size(depth: &u32, branch: &u32) -> u128 {
+        let mut size: u128 = 0;
         for i in 0..*depth {
-            size += branch.pow(i);
+            size += (*branch as u128).pow(i);
         }
         size
     }
 
     // generate a new edge from a sender to a receiver
-    fn new_edge_gen(message: &String, sid: &u32, rid: &u32) -> MsgPacket {
-        let map_id_ik = db_ik::query(&vec![*sid]);
-        let tk = tk_gen(map_id_ik.get(sid).unwrap(), rid);
+    fn new_edge_gen(message: &String, sid: &Uid, rid: &Uid) -> MsgPacket {
+        let tk = tk_gen(&IdKey::id_as_key_gen(*sid).key, rid);
         let packet = messaging::send_packet(message, &[0;16], &tk);
         let _ = db_tag::add(&vec![encode(packet.p_tag)]);
         packet
     }
 
-    fn fwd_edge_gen(message: &String, sid: &u32, rid: &u32, prev_packet: &MsgPacket, map_id_ik: &HashMap<u32, [u8;16]>) -> MsgPacket {
+    fn fwd_edge_gen(message: &String, sid: &Uid, rid: &Uid, prev_packet: &MsgPacket, map_id_ik: &HashMap<Uid, [u8;16]>) -> MsgPacket {
         let tk = tk_gen(map_id_ik.get(sid).unwrap(), rid);
-        let packet = messaging::send_packet(message, &prev_packet.tag_key, &tk);
-        packet
+        messaging::send_packet(message, &prev_packet.tag_key, &tk)
     }
 
-    fn fwd_path_gen(s_tag_key: &[u8; 16], message: &String, users: &Vec<u32>, id_keys: &HashMap<u32,[u8;16]>) -> Vec<[u8; 16]> {
+    fn fwd_path_gen(s_tag_key: &[u8; 16], message: &String, users: &[Uid], id_keys: &HashMap<Uid,[u8;16]>) -> Vec<[u8; 16]> {
         let mut tag_keys: Vec<[u8;16]> = Vec::new();
         let mut tags: Vec<String> = Vec::new();
         let mut sessions: Vec<Edge> = Vec::new();
@@ -399,16 +1958,16 @@ pub mod tests {
         tag_keys
     }
 
-    fn path_sess_gen (length: u32, sess_per_user: u32) -> (Vec<Edge>, Vec<Edge>, Vec<u32>) {
+    fn path_sess_gen (length: u32, sess_per_user: u32) -> (Vec<Edge>, Vec<Edge>, Vec<Uid>) {
         let mut padding_sessions: Vec<Edge> = Vec::new();
-        let mut users: Vec<u32> = Vec::new();
+        let mut users: Vec<Uid> = Vec::new();
         for _i in 0..length {
             // let u_1_index = i + 1;
-            users.push(rand::random::<u32>());
-            let mut sess_of_user: Vec<u32> = Vec::new();
-            sess_of_user.push(rand::random::<u32>());
+            users.push(rand::random::<Uid>());
+            let mut sess_of_user: Vec<Uid> = Vec::new();
+            sess_of_user.push(rand::random::<Uid>());
             for _j in 0..(sess_per_user-1) {
-                sess_of_user.push(rand::random::<u32>()); 
+                sess_of_user.push(rand::random::<Uid>()); 
             }
             padding_sessions.extend(mock_nbr_star(&sess_of_user));
         }
@@ -416,7 +1975,7 @@ pub mod tests {
         (fwd_sessions, padding_sessions, users)
     }
 
-    pub fn mock_nbr_line(users: &Vec<u32>) -> Vec<Edge> {
+    pub fn mock_nbr_line(users: &[Uid]) -> Vec<Edge> {
         let mut sessions: Vec<Edge> = Vec::new();
         for i in 0..(users.len()-1) {
             let ses = Edge::new(users.get(i).unwrap(), users.get(i+1).unwrap());
@@ -426,9 +1985,9 @@ pub mod tests {
         sessions
     }
 
-    pub fn mock_nbr_star(users: &Vec<u32>) -> Vec<Edge> {
+    pub fn mock_nbr_star(users: &[Uid]) -> Vec<Edge> {
         let mut sessions: Vec<Edge> = Vec::new();
-        let central = users.get(0).unwrap();
+        let central = users.first().unwrap();
         for i in 1..users.len() {
             let ses = Edge::new( central, users.get(i).unwrap());
             let vec_ses = vec![ses];
@@ -438,7 +1997,7 @@ pub mod tests {
     }
 
     // Generate rows that connects all users in the vector
-    pub fn mock_nbr_full_connect(users: &Vec<u32>) -> Vec<Edge> {
+    pub fn mock_nbr_full_connect(users: &[Uid]) -> Vec<Edge> {
         let mut sessions: Vec<Edge> = Vec::new();
         for i in 0..users.len() {
             for j in i+1..users.len() {
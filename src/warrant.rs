@@ -0,0 +1,278 @@
+#![allow(dead_code)]
+
+// The secret key that opens the sealed identity keys is split k-of-n among the moderators and is never
+// put back together. Each approving moderator opens its part of the boxes of one report instead.
+pub mod trace_warrant {
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use base64::encode;
+    use curve25519_dalek::{EdwardsPoint, MontgomeryPoint, Scalar};
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use serde::{Serialize, Deserialize};
+    use x25519_dalek::PublicKey;
+
+    use crate::db::{db_ik, db_warrant};
+    use crate::message::messaging::{MsgReport, Uid};
+    use crate::message::sealed_sender::SealedBox;
+    use crate::tool::algos::{random_scalar, shamir_split, shamir_lagrange};
+    use crate::trace::trace_scope::{TraceScope, ScopePolicy, ScopeGrant};
+    use crate::tool::utils::{hash, now};
+
+    // kept by its moderator, a warrant only borrows it to ask for partial openings
+    pub struct ModeratorShare {
+        pub moderator: Uid,
+        x: u8,
+        share: Scalar,
+    }
+
+    // the edwards point of an x25519 public key, either sign gives the same x25519 output
+    fn edwards(epk: &[u8; 32]) -> Option<EdwardsPoint> {
+        MontgomeryPoint(*epk).to_edwards(0)
+    }
+
+    impl ModeratorShare {
+        // share * B, published in the moderator set to check the share an approval comes with
+        fn verifier(&self) -> [u8; 32] {
+            EdwardsPoint::mul_base(&self.share).compress().to_bytes()
+        }
+
+        // share * epk for every box, only for a report this moderator has approved
+        pub fn partial(&self, report_hash: &[u8; 16], sealed: &HashMap<Uid, SealedBox>) -> Result<PartialOpening, WarrantError> {
+            if !db_warrant::approvals(report_hash).iter().any(|approval| approval.moderator == self.moderator) {
+                return Err(WarrantError::NotApproved);
+            }
+            let points = sealed.iter()
+                .filter_map(|(uid, sealed)| Some((*uid, (edwards(&sealed.epk)? * self.share).compress().to_bytes())))
+                .collect();
+            Ok(PartialOpening { moderator: self.moderator, report_hash: *report_hash, x: self.x, points })
+        }
+    }
+
+    // the dh shares of one moderator on the ephemeral keys of the boxes it was asked to open
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct PartialOpening {
+        pub moderator: Uid,
+        pub report_hash: [u8; 16],
+        x: u8,
+        points: HashMap<Uid, [u8; 32]>,
+    }
+
+    // Lagrange in the exponent puts the dh output on each epk together from k partials of one report,
+    // a box no partial covers stays closed
+    pub fn combine_partials(report_hash: &[u8; 16], partials: &[PartialOpening], sealed: &HashMap<Uid, SealedBox>) -> HashMap<Uid, [u8; 16]> {
+        if partials.iter().any(|partial| partial.report_hash != *report_hash) {
+            return HashMap::new();
+        }
+        let weights = shamir_lagrange(&partials.iter().map(|partial| partial.x).collect::<Vec<u8>>());
+        sealed.iter().filter_map(|(uid, sealed)| {
+            let shared = partials.iter().zip(&weights)
+                .map(|(partial, weight)| Some(CompressedEdwardsY(*partial.points.get(uid)?).decompress()? * weight))
+                .sum::<Option<EdwardsPoint>>()?;
+            Some((*uid, sealed.open_key_shared(&shared.to_montgomery().to_bytes())?))
+        }).collect()
+    }
+
+    // the public part of the setup, identity keys are sealed to `public`
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct ModeratorSet {
+        pub threshold: usize,
+        pub public: [u8; 32],
+        verifiers: HashMap<Uid, [u8; 32]>,
+        // the scopes a warrant may be issued for
+        policy: ScopePolicy,
+    }
+
+    impl ModeratorSet {
+        pub fn setup(threshold: usize, moderators: &[Uid], policy: ScopePolicy) -> (Self, Vec<ModeratorShare>) {
+            assert!((1..=moderators.len()).contains(&threshold) & (moderators.len() < 256));
+            let secret = random_scalar();
+            let public = EdwardsPoint::mul_base(&secret).to_montgomery().to_bytes();
+            let shares: Vec<ModeratorShare> = moderators.iter()
+                .zip(shamir_split(&secret, threshold as u8, moderators.len() as u8))
+                .map(|(moderator, (x, share))| ModeratorShare { moderator: *moderator, x, share })
+                .collect();
+            let verifiers = shares.iter().map(|s| (s.moderator, s.verifier())).collect();
+            (ModeratorSet { threshold, public, verifiers, policy }, shares)
+        }
+
+        pub fn public_key(&self) -> PublicKey {
+            PublicKey::from(self.public)
+        }
+
+        pub fn policy(&self) -> &ScopePolicy {
+            &self.policy
+        }
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Approval {
+        pub moderator: Uid,
+        pub report_hash: [u8; 16],
+        pub scope: TraceScope,
+        pub ts: u64,
+    }
+
+    pub fn report_hash(report: &MsgReport) -> [u8; 16] {
+        hash(&(encode(report.key) + &report.payload))
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum WarrantError {
+        UnknownModerator,
+        InvalidShare,
+        AlreadyApproved,
+        NotEnoughApprovals,
+        // a moderator is asked to open boxes for a report it has not approved
+        NotApproved,
+        // the policy of the moderator set does not allow the scope
+        ScopeNotPermitted,
+        // the warrant was issued for another report
+        NotCovered,
+        // an approval or an audit entry could not be recorded
+        Storage(String),
+    }
+
+    // collects the approvals for tracing one report within a scope, run by `operator`
+    pub struct WarrantRequest<'a> {
+        set: &'a ModeratorSet,
+        report_hash: [u8; 16],
+        scope: TraceScope,
+        operator: String,
+        approvers: Vec<&'a ModeratorShare>,
+    }
+
+    impl<'a> WarrantRequest<'a> {
+        pub fn new(set: &'a ModeratorSet, report: &MsgReport, scope: TraceScope, operator: &str) -> Self {
+            WarrantRequest { set, report_hash: report_hash(report), scope, operator: operator.to_string(), approvers: Vec::new() }
+        }
+
+        // every accepted approval is recorded in db_warrant
+        pub fn approve(&mut self, share: &'a ModeratorShare) -> Result<Approval, WarrantError> {
+            match self.set.verifiers.get(&share.moderator) {
+                None => return Err(WarrantError::UnknownModerator),
+                Some(verifier) if *verifier != share.verifier() => return Err(WarrantError::InvalidShare),
+                _ => {},
+            }
+            if self.approvers.iter().any(|s| s.moderator == share.moderator) {
+                return Err(WarrantError::AlreadyApproved);
+            }
+            let approval = Approval { moderator: share.moderator, report_hash: self.report_hash, scope: self.scope, ts: now() };
+            db_warrant::record(&approval).map_err(|e| WarrantError::Storage(e.to_string()))?;
+            self.approvers.push(share);
+            Ok(approval)
+        }
+
+        pub fn approvals(&self) -> usize {
+            self.approvers.len()
+        }
+
+        // the grant is only issued here, by the policy of the moderator set
+        pub fn issue(&self) -> Result<TraceWarrant<'a>, WarrantError> {
+            let grant = self.set.policy.authorize(self.scope, &self.operator).ok_or(WarrantError::ScopeNotPermitted)?;
+            if self.approvers.len() < self.set.threshold {
+                return Err(WarrantError::NotEnoughApprovals);
+            }
+            let moderators = self.approvers[..self.set.threshold].to_vec();
+            Ok(TraceWarrant { report_hash: self.report_hash, grant, moderators })
+        }
+    }
+
+    // Opens the sealed identity keys while tracing the report it was issued for, within its grant.
+    // Every opening combines the partials of the approving moderators, no secret is held here.
+    pub struct TraceWarrant<'a> {
+        report_hash: [u8; 16],
+        grant: ScopeGrant,
+        moderators: Vec<&'a ModeratorShare>,
+    }
+
+    impl TraceWarrant<'_> {
+        pub fn covers(&self, report: &MsgReport) -> bool {
+            report_hash(report) == self.report_hash
+        }
+
+        pub fn grant(&self) -> &ScopeGrant {
+            &self.grant
+        }
+
+        pub(crate) fn check(&self, report: &MsgReport) -> Result<(), WarrantError> {
+            match self.covers(report) {
+                true => Ok(()),
+                false => Err(WarrantError::NotCovered),
+            }
+        }
+
+        // nothing is opened for a report the warrant was not issued for
+        pub(crate) fn open_keys(&self, report: &MsgReport, sealed: &HashMap<Uid, SealedBox>) -> HashMap<Uid, [u8; 16]> {
+            if !self.covers(report) {
+                return HashMap::new();
+            }
+            let partials: Result<Vec<PartialOpening>, WarrantError> = self.moderators.iter()
+                .map(|moderator| moderator.partial(&self.report_hash, sealed))
+                .collect();
+            match partials {
+                Ok(partials) => combine_partials(&self.report_hash, &partials, sealed),
+                Err(_) => HashMap::new(),
+            }
+        }
+
+        // the registered identity keys of vec_id, read from db_ik
+        pub(crate) fn identity_keys(&self, report: &MsgReport, vec_id: &[Uid]) -> HashMap<Uid, [u8; 16]> {
+            self.open_keys(report, &db_ik::query_sealed(vec_id))
+        }
+    }
+
+    impl fmt::Debug for TraceWarrant<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "TraceWarrant({}, {:?})", hex::encode(self.report_hash), self.grant.scope())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::db::db_warrant;
+    use crate::message::messaging::{MsgReport, Uid};
+    use crate::message::sealed_sender::SealedBox;
+    use crate::trace::trace_scope::{TraceScope, ScopePolicy};
+    use crate::warrant::trace_warrant::{ModeratorSet, WarrantRequest, WarrantError, report_hash, combine_partials};
+
+    #[test]
+    fn warrant_threshold() {
+        let policy = ScopePolicy { path_to_origin: true, ..ScopePolicy::origin_only() };
+        let (set, shares) = ModeratorSet::setup(2, &[11, 12, 13].map(Uid), policy);
+        let (other_set, other_shares) = ModeratorSet::setup(2, &[11, 12, 13].map(Uid), ScopePolicy::origin_only());
+        let report = MsgReport { key: rand::random::<[u8; 16]>(), payload: "message".to_string() };
+        let ik = rand::random::<[u8; 16]>();
+        let sealed = SealedBox::seal_key(&ik, &set.public_key());
+
+        let mut request = WarrantRequest::new(&set, &report, TraceScope::PathToOrigin, "alice");
+        assert_eq!(request.approve(&other_shares[0]).unwrap_err(), WarrantError::InvalidShare);
+        assert_eq!(WarrantRequest::new(&other_set, &report, TraceScope::OriginOnly, "alice").issue().unwrap_err(), WarrantError::NotEnoughApprovals);
+        assert_eq!(WarrantRequest::new(&other_set, &report, TraceScope::PathToOrigin, "alice").issue().unwrap_err(), WarrantError::ScopeNotPermitted);
+        request.approve(&shares[2]).unwrap();
+        assert_eq!(request.approve(&shares[2]).unwrap_err(), WarrantError::AlreadyApproved);
+        assert_eq!(request.issue().unwrap_err(), WarrantError::NotEnoughApprovals);
+        request.approve(&shares[0]).unwrap();
+
+        let warrant = request.issue().unwrap();
+        assert_eq!((warrant.grant().scope(), warrant.grant().operator()), (TraceScope::PathToOrigin, "alice"));
+        let other = MsgReport { key: report.key, payload: "other".to_string() };
+        let sealed = HashMap::from([(Uid(7), sealed)]);
+        assert!(warrant.covers(&report) & !warrant.covers(&other));
+        assert_eq!(warrant.open_keys(&report, &sealed), HashMap::from([(Uid(7), ik)]));
+        assert!(warrant.open_keys(&other, &sealed).is_empty());
+        // a moderator only opens its part for a report it approved, and one part opens nothing
+        assert_eq!(shares[1].partial(&report_hash(&report), &sealed).unwrap_err(), WarrantError::NotApproved);
+        let partial = shares[0].partial(&report_hash(&report), &sealed).unwrap();
+        assert!(combine_partials(&report_hash(&report), std::slice::from_ref(&partial), &sealed).is_empty());
+        let partials = [partial, shares[2].partial(&report_hash(&report), &sealed).unwrap()];
+        assert_eq!(combine_partials(&report_hash(&report), &partials, &sealed), HashMap::from([(Uid(7), ik)]));
+        assert!(combine_partials(&report_hash(&other), &partials, &sealed).is_empty());
+        let approvers: Vec<Uid> = db_warrant::approvals(&report_hash(&report)).iter().map(|a| a.moderator).collect();
+        assert_eq!(approvers, vec![Uid(13), Uid(11)]);
+    }
+}